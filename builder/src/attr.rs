@@ -0,0 +1,186 @@
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Error, Lit, LitStr, Meta, MetaList, MetaNameValue, NestedMeta, Path, Result, Token,
+};
+
+use crate::helpers::closest_match;
+
+pub(crate) struct AttrParser {
+    name: &'static str,
+    keys: &'static [&'static str],
+    repeatable: &'static [&'static str],
+    seen: Vec<(&'static str, Path)>,
+    errors: Vec<Error>,
+}
+
+impl AttrParser {
+    pub fn new(name: &'static str, keys: &'static [&'static str]) -> Self {
+        AttrParser {
+            name,
+            keys,
+            repeatable: &[],
+            seen: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn repeatable(self, repeatable: &'static [&'static str]) -> Self {
+        AttrParser { repeatable, ..self }
+    }
+
+    pub fn builder_args(&mut self, attrs: &[Attribute]) -> Vec<Meta> {
+        let mut args = Vec::new();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("builder")) {
+            match attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+                Ok(parsed) => args.extend(parsed),
+                Err(err) => self.errors.push(err),
+            }
+        }
+
+        args
+    }
+
+    pub fn nested_args(&mut self, list: &MetaList) -> Vec<Meta> {
+        let mut args = Vec::new();
+        for nested in &list.nested {
+            match nested {
+                NestedMeta::Meta(meta) => args.push(meta.clone()),
+                NestedMeta::Lit(lit) => self.errors.push(self.unknown(lit)),
+            }
+        }
+
+        args
+    }
+
+    pub fn key(&mut self, meta: &Meta) -> Option<&'static str> {
+        let path = meta.path();
+        let Some(key) = path
+            .get_ident()
+            .and_then(|ident| self.keys.iter().find(|key| ident == *key))
+        else {
+            self.errors.push(self.unknown(path));
+            return None;
+        };
+
+        if !self.repeatable.contains(key) && self.seen(key).is_some() {
+            self.errors.push(Error::new_spanned(
+                path,
+                format!("duplicate `{}` attribute", key),
+            ));
+            return None;
+        }
+
+        self.seen.push((key, path.clone()));
+        Some(key)
+    }
+
+    pub fn record(&mut self, result: Result<()>) {
+        if let Err(err) = result {
+            self.errors.push(err);
+        }
+    }
+
+    pub fn conflicts(&mut self, pairs: &[(&str, &str)]) {
+        for &(a, b) in pairs {
+            let (Some(first), Some(second)) = (self.position(a), self.position(b)) else {
+                continue;
+            };
+            let (first, second) = if first < second {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            let (key, ref path) = self.seen[second];
+            let error = Error::new_spanned(
+                path,
+                format!("`{}` cannot be combined with `{}`", key, self.seen[first].0),
+            );
+            self.errors.push(error);
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        let mut errors = self.errors.into_iter();
+        let Some(mut error) = errors.next() else {
+            return Ok(());
+        };
+        for next in errors {
+            error.combine(next);
+        }
+
+        Err(error)
+    }
+
+    pub fn seen(&self, key: &str) -> Option<&Path> {
+        self.seen
+            .iter()
+            .find(|(seen, _)| *seen == key)
+            .map(|(_, path)| path)
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.seen.iter().position(|(seen, _)| *seen == key)
+    }
+
+    fn unknown(&self, tokens: impl ToTokens) -> Error {
+        let word = tokens.to_token_stream().to_string().replace(' ', "");
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| format!("`{}`", key))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let message = match closest_match(&word, self.keys) {
+            Some(suggestion) => format!(
+                "unknown {} attribute `{}`, did you mean `{}`? expected one of {}",
+                self.name, word, suggestion, keys
+            ),
+            None => format!(
+                "unknown {} attribute `{}`, expected one of {}",
+                self.name, word, keys
+            ),
+        };
+
+        Error::new_spanned(tokens, message)
+    }
+}
+
+pub(crate) fn flag(meta: &Meta) -> Result<()> {
+    match meta {
+        Meta::Path(_) => Ok(()),
+        _ => Err(expected(meta, "`{}` without a value")),
+    }
+}
+
+pub(crate) fn str_value(meta: &Meta) -> Result<&LitStr> {
+    match meta {
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit),
+        _ => Err(expected(meta, "`{} = \"...\"`")),
+    }
+}
+
+pub(crate) fn bool_value(meta: &Meta) -> Result<bool> {
+    match meta {
+        Meta::Path(_) => Ok(true),
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::Bool(lit),
+            ..
+        }) => Ok(lit.value),
+        _ => Err(expected(meta, "`{}` or `{} = false`")),
+    }
+}
+
+pub(crate) fn list(meta: &Meta) -> Result<&MetaList> {
+    match meta {
+        Meta::List(list) => Ok(list),
+        _ => Err(expected(meta, "`{}(...)`")),
+    }
+}
+
+pub(crate) fn expected(meta: &Meta, forms: &str) -> Error {
+    let key = meta.path().to_token_stream().to_string();
+    Error::new_spanned(meta, format!("expected {}", forms.replace("{}", &key)))
+}
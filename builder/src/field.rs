@@ -1,162 +1,640 @@
-use proc_macro2::Ident;
-use quote::{ToTokens, TokenStreamExt};
-use syn::parse::Parse;
+use proc_macro2::{Ident, Span};
+use quote::format_ident;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
 use syn::{
-    Attribute, Error, Field, GenericArgument, LitStr, Path, PathArguments, Result, Token, Type,
+    parse_quote_spanned, Error, Expr, Field, GenericArgument, Index, Lit, LitStr, Member, Meta,
+    MetaList, MetaNameValue, PathArguments, Result, Type, Visibility,
 };
 
-pub(crate) enum BuilderField<'a> {
-    Mandatory {
-        ident: &'a Ident,
-        ty: &'a Type,
-    },
+use crate::attr::{bool_value, expected, flag, list, str_value, AttrParser};
+use crate::input::{Input, Options};
+
+pub(crate) struct BuilderField<'a> {
+    pub ident: Ident,
+    pub member: Member,
+    pub ty: &'a Type,
+    pub kind: FieldKind,
+    pub vis: Option<Visibility>,
+    pub setter: SetterOptions,
+    pub validate: Option<Expr>,
+    pub env: Option<EnvVar>,
+}
+
+pub(crate) enum FieldKind {
+    Mandatory,
     Optional {
-        ident: &'a Ident,
-        ty: &'a Type,
+        inner_ty: Box<Type>,
+    },
+    Defaulted {
+        default: Option<Box<Expr>>,
     },
     Multi {
-        ident: &'a Ident,
-        attrs: Vec<Ident>,
+        each: Vec<EachSetter>,
         with_set_all: bool,
-        ty: &'a Type,
-        elem_ty: &'a Type,
+        elem: Elem,
+        merge: Merge,
     },
+    Skipped {
+        default: Option<Box<Expr>>,
+    },
+}
+
+pub(crate) struct EnvVar {
+    pub var: LitStr,
+    pub separator: Option<LitStr>,
+}
+
+pub(crate) struct EachSetter {
+    pub ident: Ident,
+    pub into: bool,
 }
 
-impl<'a> TryFrom<&'a Field> for BuilderField<'a> {
-    type Error = Error;
+#[derive(Default, Clone, Copy, PartialEq)]
+pub(crate) enum Merge {
+    #[default]
+    Append,
+    Replace,
+}
+
+pub(crate) enum Elem {
+    Item(Box<Type>),
+    Entry(Box<Type>, Box<Type>),
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct SetterOptions {
+    pub into: bool,
+    pub try_into: bool,
+    pub parse: Option<Span>,
+    pub strip_option: Option<bool>,
+    pub name: Option<Ident>,
+    pub prefix: Option<String>,
+}
 
-    fn try_from(field: &'a Field) -> Result<Self> {
-        let ident = field.ident.as_ref().unwrap();
-        let each_attrs = get_each_attrs(field)?;
+impl<'a> BuilderField<'a> {
+    pub fn from_field(field: &'a Field, index: usize, options: &Options) -> Result<Self> {
+        let ty = &field.ty;
+        let attrs = FieldAttrs::from_field(field)?;
 
-        if each_attrs.len() > 0 {
-            let elem_ty = type_behind_vec(&field.ty).unwrap();
-            let with_set_all = each_attrs.iter().all(|attr| attr != ident);
+        let member = match field.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: index as u32,
+                span: ty.span(),
+            }),
+        };
+        let ident = match (attrs.name, &field.ident) {
+            (Some(name), _) => name,
+            (None, Some(ident)) => ident.clone(),
+            (None, None) => format_ident!("_{}", index, span = ty.span()),
+        };
 
-            Ok(BuilderField::Multi {
-                ident,
+        if let Some(parse) = attrs.setter.parse {
+            if !attrs.each.is_empty() {
+                return Err(Error::new(
+                    parse,
+                    "`setter(parse)` cannot be combined with `each`",
+                ));
+            }
+            if options.typestate {
+                return Err(Error::new(
+                    parse,
+                    "`setter(parse)` is not supported by typestate builders, use `setter(try_into)` instead",
+                ));
+            }
+        }
+
+        let setter = SetterOptions {
+            into: attrs.setter.into || options.setter.into,
+            try_into: attrs.setter.try_into || options.setter.try_into,
+            parse: attrs.setter.parse,
+            strip_option: attrs.setter.strip_option.or(options.setter.strip_option),
+            name: attrs.setter.name,
+            prefix: attrs
+                .setter
+                .prefix
+                .or_else(|| options.setter.prefix.clone()),
+        };
+
+        if let Some(ref env) = attrs.env {
+            if options.typestate || options.no_std {
+                return Err(Error::new(
+                    env.var.span(),
+                    "`env` is not supported by typestate or no_std builders",
+                ));
+            }
+            if let (Some(ref separator), true) = (&env.separator, attrs.each.is_empty()) {
+                return Err(Error::new(
+                    separator.span(),
+                    "`separator` can only be used together with `each`",
+                ));
+            }
+        }
+
+        if let Some(ref merge) = attrs.merge {
+            if options.typestate {
+                return Err(Error::new(
+                    merge.span(),
+                    "`merge` is not supported by typestate builders",
+                ));
+            }
+            if attrs.each.is_empty() {
+                return Err(Error::new(
+                    merge.span(),
+                    "`merge` can only be used together with `each`",
+                ));
+            }
+        }
+
+        let kind = if let Some(skip) = attrs.skip {
+            FieldKind::Skipped { default: skip.expr }
+        } else if !attrs.each.is_empty() {
+            if !is_collection_like(ty) {
+                return Err(Error::new_spanned(
+                    ty,
+                    "`each` requires a collection type such as `Vec<T>`, `HashSet<T>` or `HashMap<K, V>`",
+                ));
+            }
+
+            let elem = collection_elem(ty);
+            let setter_ident = setter.setter_ident(&ident);
+            let with_set_all = attrs
+                .each
+                .iter()
+                .all(|each| setter.prefixed(&each.ident) != setter_ident);
+
+            FieldKind::Multi {
                 with_set_all,
-                attrs: each_attrs,
-                ty: &field.ty,
-                elem_ty,
-            })
-        } else if let Some(ty) = type_behind_option(&field.ty) {
-            Ok(BuilderField::Optional { ident, ty })
+                each: attrs.each,
+                elem,
+                merge: attrs
+                    .merge
+                    .as_ref()
+                    .map(Merge::from_lit)
+                    .transpose()?
+                    .unwrap_or_default(),
+            }
+        } else if let Some(default) = attrs.default {
+            FieldKind::Defaulted {
+                default: default.expr,
+            }
+        } else if attrs.required.is_some() {
+            FieldKind::Mandatory
+        } else if is_phantom_data(ty) {
+            FieldKind::Skipped { default: None }
+        } else if let Some(inner_ty) = type_behind_option(ty) {
+            FieldKind::Optional {
+                inner_ty: Box::new(inner_ty.clone()),
+            }
+        } else if attrs.optional.is_some() {
+            FieldKind::Optional {
+                inner_ty: Box::new(parse_quote_spanned! { ty.span() =>
+                    <#ty as ::core::iter::IntoIterator>::Item
+                }),
+            }
         } else {
-            Ok(BuilderField::Mandatory {
-                ident,
-                ty: &field.ty,
-            })
+            FieldKind::Mandatory
+        };
+
+        Ok(BuilderField {
+            ident,
+            member,
+            ty,
+            kind,
+            vis: attrs.vis,
+            validate: attrs.validate,
+            env: attrs.env,
+            setter,
+        })
+    }
+
+    pub fn setter_vis<'b>(&'b self, input: &'b Input) -> &'b Visibility {
+        self.vis.as_ref().unwrap_or_else(|| input.builder_vis())
+    }
+
+    pub fn setter_ident(&self) -> Ident {
+        self.setter.setter_ident(&self.ident)
+    }
+
+    pub fn prefixed(&self, ident: &Ident) -> Ident {
+        self.setter.prefixed(ident)
+    }
+
+    pub fn is_mandatory(&self) -> bool {
+        matches!(self.kind, FieldKind::Mandatory)
+    }
+
+    pub fn can_be_invalid(&self) -> bool {
+        self.validate.is_some() || self.setter.parse.is_some()
+    }
+
+    pub fn error_slot(&self) -> Option<Ident> {
+        (self.setter.parse.is_some() || self.env.is_some())
+            .then(|| format_ident!("__{}_error", self.ident.unraw()))
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.kind, FieldKind::Skipped { .. })
+    }
+
+    pub fn companion_ident(&self, prefix: &str) -> Ident {
+        format_ident!("{}_{}", prefix, self.setter_ident().unraw())
+    }
+
+    pub fn method_names(&self) -> Vec<Ident> {
+        let setter_ident = self.setter_ident();
+        match self.kind {
+            FieldKind::Skipped { .. } => vec![],
+            FieldKind::Multi {
+                ref each,
+                with_set_all,
+                ..
+            } => with_set_all
+                .then_some(setter_ident)
+                .into_iter()
+                .chain(each.iter().map(|each| self.prefixed(&each.ident)))
+                .chain([
+                    self.companion_ident("extend"),
+                    self.companion_ident("clear"),
+                ])
+                .collect(),
+            FieldKind::Mandatory | FieldKind::Optional { .. } | FieldKind::Defaulted { .. } => {
+                let try_ident = self.setter.try_into.then(|| self.companion_ident("try"));
+                let (maybe, unset) = match self.kind {
+                    FieldKind::Optional { .. } => (
+                        self.setter
+                            .strips_option()
+                            .then(|| self.companion_ident("maybe")),
+                        Some(self.companion_ident("unset")),
+                    ),
+                    _ => (None, None),
+                };
+
+                [Some(setter_ident), try_ident, maybe, unset]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            }
+        }
+    }
+
+    pub fn setter_ty(&self) -> &Type {
+        match self.kind {
+            FieldKind::Optional { ref inner_ty } => inner_ty,
+            _ => self.ty,
         }
     }
 }
 
-#[derive(Debug)]
-struct EachArg {
-    each: Ident,
-    _equals_token: Token![=],
-    alias: LitStr,
+impl SetterOptions {
+    pub fn parse_nested(&mut self, list: &MetaList) -> Result<()> {
+        let mut parser = AttrParser::new("setter", SETTER_KEYS);
+
+        for arg in parser.nested_args(list) {
+            let result = match parser.key(&arg) {
+                Some("into") => flag(&arg).map(|()| self.into = true),
+                Some("try_into") => flag(&arg).map(|()| self.try_into = true),
+                Some("parse") => flag(&arg).map(|()| self.parse = Some(arg.span())),
+                Some("name") => str_value(&arg)
+                    .and_then(LitStr::parse)
+                    .map(|name| self.name = Some(name)),
+                Some("prefix") => str_value(&arg).and_then(|prefix| {
+                    if syn::parse_str::<Ident>(&format!("{}setter", prefix.value())).is_err() {
+                        return Err(Error::new(prefix.span(), "invalid setter prefix"));
+                    }
+                    self.prefix = Some(prefix.value());
+                    Ok(())
+                }),
+                Some("strip_option") => {
+                    bool_value(&arg).map(|strip_option| self.strip_option = Some(strip_option))
+                }
+                _ => continue,
+            };
+            parser.record(result);
+        }
+
+        parser.conflicts(&[("parse", "into"), ("parse", "try_into")]);
+        parser.finish()
+    }
+
+    pub fn strips_option(&self) -> bool {
+        self.strip_option.unwrap_or(true)
+    }
+
+    fn setter_ident(&self, ident: &Ident) -> Ident {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => self.prefixed(ident),
+        }
+    }
+
+    fn prefixed(&self, ident: &Ident) -> Ident {
+        match self.prefix {
+            Some(ref prefix) => format_ident!("{}{}", prefix, ident.unraw(), span = ident.span()),
+            None => ident.clone(),
+        }
+    }
 }
 
-impl Parse for EachArg {
-    fn parse(input: syn::parse::ParseStream) -> Result<Self> {
-        let arg = EachArg {
-            each: input.parse()?,
-            _equals_token: input.parse()?,
-            alias: input.parse()?,
+impl EnvVar {
+    fn parse_nested(list: &MetaList) -> Result<Self> {
+        let mut parser = AttrParser::new("env", &["name", "separator"]);
+        let mut var = None;
+        let mut separator = None;
+
+        for arg in parser.nested_args(list) {
+            let result = match parser.key(&arg) {
+                Some("name") => str_value(&arg).map(|name| var = Some(name.clone())),
+                Some("separator") => str_value(&arg).map(|sep| separator = Some(sep.clone())),
+                _ => continue,
+            };
+            parser.record(result);
+        }
+
+        parser.finish()?;
+
+        let Some(var) = var else {
+            return Err(Error::new_spanned(list, "`env(...)` requires a `name`"));
         };
 
-        if arg.each.to_string() != "each" {
-            return Err(Error::new_spanned(arg.each, "expected \"each\""));
+        Ok(EnvVar { var, separator })
+    }
+}
+
+impl EachSetter {
+    fn parse_nested(list: &MetaList) -> Result<Self> {
+        let mut parser = AttrParser::new("each", &["name", "into"]);
+        let mut ident = None;
+        let mut into = false;
+
+        for arg in parser.nested_args(list) {
+            let result = match parser.key(&arg) {
+                Some("name") => str_value(&arg)
+                    .and_then(LitStr::parse)
+                    .map(|name| ident = Some(name)),
+                Some("into") => flag(&arg).map(|()| into = true),
+                _ => continue,
+            };
+            parser.record(result);
         }
 
-        Ok(arg)
+        parser.finish()?;
+
+        let Some(ident) = ident else {
+            return Err(Error::new_spanned(list, "`each(...)` requires a `name`"));
+        };
+
+        Ok(EachSetter { ident, into })
     }
 }
 
-fn get_each_attrs(field: &Field) -> Result<Vec<Ident>> {
-    field
-        .attrs
-        .iter()
-        .filter_map(|attr| {
-            if !path_is_ident(&attr.path, "builder") {
-                return None;
-            }
+impl Merge {
+    fn from_lit(lit: &LitStr) -> Result<Self> {
+        match lit.value().as_str() {
+            "append" => Ok(Merge::Append),
+            "replace" => Ok(Merge::Replace),
+            _ => Err(Error::new(
+                lit.span(),
+                "expected one of \"append\" or \"replace\"",
+            )),
+        }
+    }
+}
 
-            Some(parse_each_attr(attr))
-        })
-        .collect()
+#[derive(Default)]
+struct FieldAttrs {
+    name: Option<Ident>,
+    vis: Option<Visibility>,
+    validate: Option<Expr>,
+    each: Vec<EachSetter>,
+    default: Option<DefaultArg>,
+    skip: Option<DefaultArg>,
+    merge: Option<LitStr>,
+    env: Option<EnvVar>,
+    optional: Option<Span>,
+    required: Option<Span>,
+    setter: SetterOptions,
 }
 
-fn parse_each_attr(attr: &Attribute) -> Result<Ident> {
-    let arg: EachArg = attr.parse_args().map_err(|_| {
-        let mut error_tokens = attr.tokens.clone();
-        error_tokens.append_all(attr.path.segments.to_token_stream());
-        Error::new_spanned(error_tokens, "expected `builder(each = \"...\")`")
-    })?;
-    Ok(Ident::new(&arg.alias.value(), arg.alias.span()))
+struct DefaultArg {
+    expr: Option<Box<Expr>>,
 }
 
-fn path_is_ident(path: &Path, ident: &str) -> bool {
-    let Some(path_ident) = path
-            .get_ident() else {
-                return false;
+impl FieldAttrs {
+    fn from_field(field: &Field) -> Result<Self> {
+        let mut attrs = FieldAttrs::default();
+        let mut parser = AttrParser::new("builder", FIELD_KEYS).repeatable(&["each"]);
+
+        for arg in parser.builder_args(&field.attrs) {
+            let Some(key) = parser.key(&arg) else {
+                continue;
             };
+            let result = attrs.parse_arg(key, &arg);
+            parser.record(result);
+        }
 
-    path_ident == ident
-}
+        parser.conflicts(FIELD_CONFLICTS);
+        parser.finish()?;
 
-fn type_behind_option(ty: &Type) -> Option<&Type> {
-    let Type::Path(syn::TypePath {
-            qself: None,
-            path: syn::Path { ref segments, .. },
-            ..
-        }) = ty else {return None};
+        Ok(attrs)
+    }
 
-    let last_segment = segments.last()?;
-    if last_segment.ident != "Option" {
-        return None;
+    fn parse_arg(&mut self, key: &str, arg: &Meta) -> Result<()> {
+        match (key, arg) {
+            ("each", Meta::List(list)) => self.each.push(EachSetter::parse_nested(list)?),
+            ("each", _) => {
+                let alias =
+                    str_value(arg).map_err(|_| expected(arg, "`each = \"...\"` or `each(...)`"))?;
+                self.each.push(EachSetter {
+                    ident: alias.parse()?,
+                    into: false,
+                })
+            }
+            ("name", _) => self.name = Some(str_value(arg)?.parse()?),
+            ("vis", _) => self.vis = Some(str_value(arg)?.parse()?),
+            ("private", _) => {
+                flag(arg)?;
+                self.vis = Some(Visibility::Inherited)
+            }
+            ("validate", _) => self.validate = Some(str_value(arg)?.parse()?),
+            ("default", _) => self.default = Some(DefaultArg::from_meta(arg)?),
+            ("skip", _) => self.skip = Some(DefaultArg::from_meta(arg)?),
+            ("optional", _) => {
+                flag(arg)?;
+                self.optional = Some(arg.span())
+            }
+            ("required", _) => {
+                flag(arg)?;
+                self.required = Some(arg.span())
+            }
+            ("merge", _) => self.merge = Some(str_value(arg)?.clone()),
+            ("env", Meta::List(list)) => self.env = Some(EnvVar::parse_nested(list)?),
+            ("env", _) => {
+                let var =
+                    str_value(arg).map_err(|_| expected(arg, "`env = \"...\"` or `env(...)`"))?;
+                self.env = Some(EnvVar {
+                    var: var.clone(),
+                    separator: None,
+                })
+            }
+            ("setter", _) => self.setter.parse_nested(list(arg)?)?,
+            _ => unreachable!(),
+        }
+
+        Ok(())
     }
+}
 
-    let PathArguments::AngleBracketed(ref args) = last_segment.arguments else {
-        return None;
-    };
+impl DefaultArg {
+    fn from_meta(meta: &Meta) -> Result<Self> {
+        let expr = match meta {
+            Meta::Path(_) => None,
+            Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(expr),
+                ..
+            }) => Some(Box::new(expr.parse()?)),
+            _ => return Err(expected(meta, "`{}` or `{} = \"...\"`")),
+        };
 
-    if args.args.len() != 1 {
-        return None;
+        Ok(DefaultArg { expr })
+    }
+}
+
+fn type_behind_option(ty: &Type) -> Option<&Type> {
+    match known_type_args(ty, "Option", OPTION_MODULES)?[..] {
+        [inner_ty] => Some(inner_ty),
+        _ => None,
     }
+}
 
-    let GenericArgument::Type(ty) = args.args.first()? else {
-        return None;
+fn is_phantom_data(ty: &Type) -> bool {
+    known_type_args(ty, "PhantomData", PHANTOM_DATA_MODULES).is_some()
+}
+
+fn is_collection_like(ty: &Type) -> bool {
+    let Type::Path(syn::TypePath { qself: None, path }) = ty else {
+        return false;
     };
 
-    Some(ty)
+    match path.get_ident() {
+        Some(ident) => !SCALAR_TYPES.iter().any(|scalar| ident == scalar),
+        None => type_behind_option(ty).is_none() && !is_phantom_data(ty),
+    }
 }
 
-fn type_behind_vec(ty: &Type) -> Option<&Type> {
-    let Type::Path(syn::TypePath {
-            qself: None,
-            path: syn::Path { ref segments, .. },
-            ..
-        }) = ty else {return None};
+fn collection_elem(ty: &Type) -> Elem {
+    for (name, modules) in MAP_TYPES {
+        if let Some([key_ty, value_ty, ..]) = known_type_args(ty, name, modules).as_deref() {
+            return Elem::Entry(Box::new((*key_ty).clone()), Box::new((*value_ty).clone()));
+        }
+    }
 
-    let last_segment = segments.last()?;
-    if last_segment.ident != "Vec" {
-        return None;
+    for (name, modules) in SEQ_TYPES {
+        if let Some([elem_ty, ..]) = known_type_args(ty, name, modules).as_deref() {
+            return Elem::Item(Box::new((*elem_ty).clone()));
+        }
     }
 
-    let PathArguments::AngleBracketed(ref args) = last_segment.arguments else {
+    Elem::Item(Box::new(
+        parse_quote_spanned! { ty.span() => <#ty as ::core::iter::IntoIterator>::Item },
+    ))
+}
+
+fn known_type_args<'a>(ty: &'a Type, name: &str, modules: &[&str]) -> Option<Vec<&'a Type>> {
+    let Type::Path(syn::TypePath {
+        qself: None,
+        path: syn::Path { ref segments, .. },
+    }) = ty
+    else {
         return None;
     };
 
-    if args.args.len() < 1 {
+    let last_segment = segments.last()?;
+    let mut module = String::new();
+    for segment in segments.iter().take(segments.len() - 1) {
+        if !segment.arguments.is_empty() {
+            return None;
+        }
+        if !module.is_empty() {
+            module.push_str("::");
+        }
+        module.push_str(&segment.ident.to_string());
+    }
+
+    if last_segment.ident != name || !(module.is_empty() || modules.contains(&module.as_str())) {
         return None;
     }
 
-    let GenericArgument::Type(ty) = args.args.first()? else {
+    let PathArguments::AngleBracketed(ref args) = last_segment.arguments else {
         return None;
     };
 
-    Some(ty)
+    Some(
+        args.args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+    )
 }
+
+const OPTION_MODULES: &[&str] = &["std::option", "core::option"];
+
+const PHANTOM_DATA_MODULES: &[&str] = &["std::marker", "core::marker"];
+
+const MAP_TYPES: &[(&str, &[&str])] = &[
+    ("HashMap", &["std::collections"]),
+    ("BTreeMap", &["std::collections", "alloc::collections"]),
+];
+
+const SEQ_TYPES: &[(&str, &[&str])] = &[
+    ("Vec", &["std::vec", "alloc::vec"]),
+    ("VecDeque", &["std::collections", "alloc::collections"]),
+    ("LinkedList", &["std::collections", "alloc::collections"]),
+    ("HashSet", &["std::collections"]),
+    ("BTreeSet", &["std::collections", "alloc::collections"]),
+    ("BinaryHeap", &["std::collections", "alloc::collections"]),
+];
+
+const FIELD_KEYS: &[&str] = &[
+    "each", "name", "vis", "private", "validate", "default", "skip", "optional", "required",
+    "merge", "env", "setter",
+];
+
+const FIELD_CONFLICTS: &[(&str, &str)] = &[
+    ("each", "default"),
+    ("each", "optional"),
+    ("each", "required"),
+    ("optional", "required"),
+    ("default", "optional"),
+    ("default", "required"),
+    ("vis", "private"),
+    ("skip", "each"),
+    ("skip", "default"),
+    ("skip", "validate"),
+    ("skip", "env"),
+    ("skip", "optional"),
+    ("skip", "required"),
+    ("skip", "merge"),
+    ("skip", "name"),
+    ("skip", "vis"),
+    ("skip", "private"),
+    ("skip", "setter"),
+];
+
+const SETTER_KEYS: &[&str] = &[
+    "into",
+    "try_into",
+    "parse",
+    "strip_option",
+    "name",
+    "prefix",
+];
+
+const SCALAR_TYPES: &[&str] = &[
+    "bool", "char", "str", "String", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
+    "i32", "i64", "i128", "isize", "f32", "f64",
+];
@@ -1,27 +1,436 @@
-use proc_macro2::{Ident, TokenStream};
-use quote::quote_spanned;
-use syn::{DataStruct, DeriveInput, Fields};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DataEnum, DataStruct, DeriveInput, Error, Expr, Fields, Generics, LitStr,
+    Meta, MetaList, NestedMeta, Path, Visibility,
+};
 
-use crate::{field::BuilderField, helpers::CollectErrorTokensExt};
+use crate::attr::{flag, list, str_value, AttrParser};
+use crate::{
+    field::{BuilderField, FieldKind, SetterOptions},
+    helpers::{CollectErrorTokensExt, CollectErrorsExt},
+};
 
 pub(crate) struct Input<'a> {
     pub ident: &'a Ident,
+    pub variant: Option<&'a Ident>,
+    pub vis: &'a Visibility,
+    pub generics: &'a Generics,
+    pub options: Options,
     pub fields: Vec<BuilderField<'a>>,
 }
 
-pub(crate) fn parse_input<'a>(input: &'a DeriveInput) -> Result<Input<'a>, TokenStream> {
-    let ident = &input.ident;
-    let fields = match input.data {
-        syn::Data::Struct(DataStruct {
-            fields: Fields::Named { 0: ref fields },
-            ..
-        }) => fields,
-        _ => return Err(quote_spanned! { input.ident.span() => compile_error!("only classic structs are supported"); }),
+#[derive(Default, Clone)]
+pub(crate) struct Options {
+    pub typestate: bool,
+    pub no_std: bool,
+    pub to_builder: Option<Span>,
+    pub name: Option<Ident>,
+    pub constructor: Option<Ident>,
+    pub vis: Option<Visibility>,
+    pub derives: Vec<Path>,
+    pub attrs: Vec<Meta>,
+    pub pattern: Pattern,
+    pub setter: SetterOptions,
+    pub build_fn: BuildFnOptions,
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct BuildFnOptions {
+    pub name: Option<Ident>,
+    pub clone: bool,
+    pub validate: Option<Expr>,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+pub(crate) enum Pattern {
+    #[default]
+    Mutable,
+    Owned,
+    Immutable,
+}
+
+pub(crate) fn parse_input<'a>(input: &'a DeriveInput) -> Result<Vec<Input<'a>>, TokenStream> {
+    match Options::from_attrs(&input.attrs) {
+        Ok(options) => parse_data(input, options),
+        Err(err) => {
+            let mut errors = err.to_compile_error();
+            errors.extend(parse_data(input, Options::default()).err());
+            Err(errors)
+        }
+    }
+}
+
+fn parse_data<'a>(input: &'a DeriveInput, options: Options) -> Result<Vec<Input<'a>>, TokenStream> {
+    match input.data {
+        Data::Struct(DataStruct { ref fields, .. }) => {
+            Ok(vec![Input::new(input, None, fields, options)?])
+        }
+        Data::Enum(DataEnum { ref variants, .. }) => {
+            if let Some(name) = options.name.as_ref().or(options.constructor.as_ref()) {
+                return Err(Error::new_spanned(
+                    name,
+                    "`name` and `constructor` are not supported on enums",
+                )
+                .to_compile_error());
+            }
+            if let Some(span) = options.to_builder {
+                return Err(
+                    Error::new(span, "`to_builder` is not supported on enums").to_compile_error()
+                );
+            }
+
+            let variant_errors = variants
+                .iter()
+                .flat_map(|variant| &variant.attrs)
+                .filter(|attr| attr.path.is_ident("builder"))
+                .map(|attr| {
+                    Error::new_spanned(
+                        attr,
+                        "`#[builder(...)]` is not supported on enum variants, put it on the enum instead",
+                    )
+                    .to_compile_error()
+                });
+            let inputs = variants
+                .iter()
+                .filter(|variant| !matches!(variant.fields, Fields::Unit))
+                .map(|variant| {
+                    Input::new(
+                        input,
+                        Some(&variant.ident),
+                        &variant.fields,
+                        options.clone(),
+                    )
+                })
+                .collect_errors();
+            let mut errors: TokenStream = variant_errors.collect();
+            let inputs = match inputs {
+                Ok(inputs) if errors.is_empty() => inputs,
+                Ok(_) => return Err(errors),
+                Err(errs) => {
+                    errors.extend(errs);
+                    return Err(errors);
+                }
+            };
+
+            if inputs.is_empty() {
+                return Err(
+                    quote_spanned! { input.ident.span() => compile_error!("enums need at least one variant with fields"); },
+                );
+            }
+
+            Ok(inputs)
+        }
+        Data::Union(_) => Err(
+            quote_spanned! { input.ident.span() => compile_error!("unions are not supported"); },
+        ),
+    }
+}
+
+impl<'a> Input<'a> {
+    fn new(
+        input: &'a DeriveInput,
+        variant: Option<&'a Ident>,
+        fields: &'a Fields,
+        options: Options,
+    ) -> Result<Self, TokenStream> {
+        let fields = fields
+            .iter()
+            .enumerate()
+            .map(|(index, f)| BuilderField::from_field(f, index, &options))
+            .collect_errors_to_stream()?;
+        check_method_names(&fields, &options)?;
+        check_default_order(&fields)?;
+
+        Ok(Input {
+            ident: &input.ident,
+            variant,
+            vis: &input.vis,
+            generics: &input.generics,
+            options,
+            fields,
+        })
     }
-    .named
-    .iter()
-    .map(|f| f.try_into())
-    .collect_errors_to_stream()?;
 
-    Ok(Input { ident, fields })
+    pub fn builder_vis(&self) -> &Visibility {
+        self.options.vis.as_ref().unwrap_or(self.vis)
+    }
+
+    pub fn target(&self) -> TokenStream {
+        let ident = self.ident;
+        match self.variant {
+            Some(variant) => quote! { #ident::#variant },
+            None => quote! { #ident },
+        }
+    }
 }
+
+impl Options {
+    fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Options::default();
+        let mut parser = AttrParser::new("builder", STRUCT_KEYS).repeatable(&["derive", "attr"]);
+
+        for arg in parser.builder_args(attrs) {
+            let Some(key) = parser.key(&arg) else {
+                continue;
+            };
+            let result = options.parse_arg(key, &arg);
+            parser.record(result);
+        }
+
+        if let Some(pattern) = parser.seen("pattern") {
+            if options.typestate && options.pattern != Pattern::Owned {
+                let error =
+                    Error::new_spanned(pattern, "typestate builders always use the owned pattern");
+                parser.record(Err(error));
+            }
+        }
+
+        parser.finish()?;
+
+        Ok(options)
+    }
+
+    fn parse_arg(&mut self, key: &str, arg: &Meta) -> syn::Result<()> {
+        match key {
+            "typestate" => {
+                flag(arg)?;
+                self.typestate = true
+            }
+            "no_std" => {
+                flag(arg)?;
+                self.no_std = true
+            }
+            "to_builder" => {
+                flag(arg)?;
+                self.to_builder = Some(arg.span())
+            }
+            "vis" => self.vis = Some(str_value(arg)?.parse()?),
+            "name" => self.name = Some(str_value(arg)?.parse()?),
+            "constructor" => self.constructor = Some(str_value(arg)?.parse()?),
+            "pattern" => {
+                let pattern = str_value(arg)?;
+                self.pattern = Pattern::from_lit(pattern)?
+            }
+            "setter" => {
+                self.setter.parse_nested(list(arg)?)?;
+                if let Some(ref name) = self.setter.name {
+                    return Err(Error::new_spanned(
+                        name,
+                        "`setter(name = \"...\")` can only be used on fields",
+                    ));
+                }
+                if let Some(parse) = self.setter.parse {
+                    return Err(Error::new(
+                        parse,
+                        "`setter(parse)` can only be used on fields",
+                    ));
+                }
+            }
+            "build_fn" => self.build_fn.parse_nested(list(arg)?)?,
+            "derive" => {
+                for nested in &list(arg)?.nested {
+                    match nested {
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("Default") => {
+                            return Err(Error::new_spanned(
+                                path,
+                                "the builder always implements `Default`",
+                            ))
+                        }
+                        NestedMeta::Meta(Meta::Path(path)) => self.derives.push(path.clone()),
+                        _ => {
+                            return Err(Error::new_spanned(nested, "expected `derive(Trait, ...)`"))
+                        }
+                    }
+                }
+            }
+            "attr" => {
+                for nested in &list(arg)?.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => self.attrs.push(meta.clone()),
+                        NestedMeta::Lit(_) => {
+                            return Err(Error::new_spanned(
+                                nested,
+                                "expected `attr(...)` to contain attributes",
+                            ))
+                        }
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+fn check_method_names(fields: &[BuilderField], options: &Options) -> Result<(), TokenStream> {
+    let mut builder_methods = vec![
+        format_ident!("new"),
+        options
+            .build_fn
+            .name
+            .clone()
+            .unwrap_or_else(|| format_ident!("build")),
+    ];
+    if !options.typestate {
+        builder_methods.push(format_ident!("merge"));
+    }
+    if fields.iter().any(|field| field.env.is_some()) {
+        builder_methods.extend([format_ident!("fill_from_env"), format_ident!("from_env")]);
+    }
+
+    let mut seen: Vec<(Ident, &Ident)> = Vec::new();
+    fields
+        .iter()
+        .flat_map(|field| {
+            field
+                .method_names()
+                .into_iter()
+                .map(move |name| (name, field))
+        })
+        .map(|(name, field)| {
+            let name = name.unraw();
+            if builder_methods.contains(&name) {
+                return Err(Error::new(
+                    name.span(),
+                    format!(
+                        "setter `{}` of field `{}` collides with a builder method of the same name",
+                        name, field.ident
+                    ),
+                ));
+            }
+            if let Some((_, other)) = seen.iter().find(|(seen, _)| *seen == name) {
+                return Err(Error::new(
+                    name.span(),
+                    format!(
+                        "setter `{}` of field `{}` collides with a setter of field `{}`",
+                        name, field.ident, other
+                    ),
+                ));
+            }
+
+            seen.push((name, &field.ident));
+            Ok(())
+        })
+        .collect_errors_to_stream()
+        .map(drop)
+}
+
+fn check_default_order(fields: &[BuilderField]) -> Result<(), TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| match field.kind {
+            FieldKind::Defaulted {
+                default: Some(ref expr),
+            }
+            | FieldKind::Skipped {
+                default: Some(ref expr),
+            } => Some((index, field, expr)),
+            _ => None,
+        })
+        .map(|(index, field, expr)| {
+            let later: Vec<&Ident> = fields[index + 1..]
+                .iter()
+                .filter(|later| {
+                    matches!(later.kind, FieldKind::Defaulted { .. } | FieldKind::Skipped { .. })
+                })
+                .map(|later| &later.ident)
+                .collect();
+            match find_binding(expr.to_token_stream(), &later) {
+                Some(ident) => Err(Error::new(
+                    ident.span(),
+                    format!(
+                        "the default of `{}` refers to `{}`, which is defaulted after it; defaults are resolved in field order",
+                        field.ident, ident
+                    ),
+                )),
+                None => Ok(()),
+            }
+        })
+        .collect_errors_to_stream()
+        .map(drop)
+}
+
+fn find_binding(tokens: TokenStream, idents: &[&Ident]) -> Option<Ident> {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut after_path_or_field = false;
+    while let Some(token) = tokens.next() {
+        let is_call = matches!(
+            tokens.peek(),
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis
+        );
+        match token {
+            TokenTree::Ident(ref ident)
+                if !after_path_or_field && !is_call && idents.contains(&ident) =>
+            {
+                return Some(ident.clone())
+            }
+            TokenTree::Group(ref group) => {
+                if let Some(ident) = find_binding(group.stream(), idents) {
+                    return Some(ident);
+                }
+            }
+            _ => {}
+        }
+        after_path_or_field =
+            matches!(token, TokenTree::Punct(ref punct) if matches!(punct.as_char(), '.' | ':'));
+    }
+
+    None
+}
+
+impl Pattern {
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "mutable" => Ok(Pattern::Mutable),
+            "owned" => Ok(Pattern::Owned),
+            "immutable" => Ok(Pattern::Immutable),
+            _ => Err(Error::new(
+                lit.span(),
+                "expected one of \"mutable\", \"owned\" or \"immutable\"",
+            )),
+        }
+    }
+}
+
+impl BuildFnOptions {
+    fn parse_nested(&mut self, list: &MetaList) -> syn::Result<()> {
+        let mut parser = AttrParser::new("build_fn", BUILD_FN_KEYS);
+
+        for arg in parser.nested_args(list) {
+            let result = match parser.key(&arg) {
+                Some("name") => str_value(&arg)
+                    .and_then(LitStr::parse)
+                    .map(|name| self.name = Some(name)),
+                Some("clone") => flag(&arg).map(|()| self.clone = true),
+                Some("validate") => str_value(&arg)
+                    .and_then(LitStr::parse)
+                    .map(|validate| self.validate = Some(validate)),
+                _ => continue,
+            };
+            parser.record(result);
+        }
+
+        parser.finish()
+    }
+}
+
+const STRUCT_KEYS: &[&str] = &[
+    "typestate",
+    "no_std",
+    "to_builder",
+    "vis",
+    "name",
+    "constructor",
+    "pattern",
+    "setter",
+    "build_fn",
+    "derive",
+    "attr",
+];
+
+const BUILD_FN_KEYS: &[&str] = &["name", "clone", "validate"];
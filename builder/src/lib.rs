@@ -1,10 +1,12 @@
 use output::{
-    output_build_method, output_builder_constructor, output_builder_type, output_setters,
+    output_build_method, output_builder_constructor, output_builder_type, output_conversions,
+    output_env, output_error_type, output_merge, output_setters,
 };
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::DeriveInput;
 
+mod attr;
 mod field;
 mod helpers;
 mod input;
@@ -19,20 +21,32 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 }
 
 fn builder(input: &DeriveInput) -> TokenStream {
-    let input = match input::parse_input(input) {
+    let inputs = match input::parse_input(input) {
         Ok(value) => value,
         Err(errs) => return errs,
     };
 
-    let ty = output_builder_type(&input);
-    let constructor = output_builder_constructor(&input);
-    let setters = output_setters(&input);
-    let builder = output_build_method(&input);
+    inputs.iter().map(builder_for).collect()
+}
+
+fn builder_for(input: &input::Input) -> TokenStream {
+    let ty = output_builder_type(input);
+    let constructor = output_builder_constructor(input);
+    let setters = output_setters(input);
+    let merge = output_merge(input);
+    let env = output_env(input);
+    let builder = output_build_method(input);
+    let error = output_error_type(input);
+    let conversions = output_conversions(input);
 
     quote! {
         #ty
         #constructor
         #setters
+        #merge
+        #env
         #builder
+        #error
+        #conversions
     }
 }
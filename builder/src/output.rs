@@ -1,135 +1,1395 @@
-use crate::{field::BuilderField, input::Input};
+use crate::{
+    field::{BuilderField, Elem, FieldKind, Merge},
+    helpers::{respan, to_camel_case, to_snake_case},
+    input::{Input, Pattern},
+};
 use proc_macro2::{Ident, TokenStream};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{
+    ext::IdentExt, spanned::Spanned, Expr, GenericParam, Generics, LitStr, Type, Visibility,
+};
 
 pub(crate) fn builder_ident(input: &Input) -> Ident {
-    format_ident!("{}Builder", input.ident)
+    if let Some(ref name) = input.options.name {
+        return name.clone();
+    }
+
+    match input.variant {
+        Some(variant) => format_ident!("{}{}Builder", input.ident, variant),
+        None => format_ident!("{}Builder", input.ident),
+    }
+}
+
+fn constructor_ident(input: &Input) -> Ident {
+    if let Some(ref constructor) = input.options.constructor {
+        return constructor.clone();
+    }
+
+    match input.variant {
+        Some(variant) => format_ident!("{}_builder", to_snake_case(variant)),
+        None => format_ident!("builder"),
+    }
+}
+
+pub(crate) fn error_ident(input: &Input) -> Ident {
+    format_ident!("{}Error", builder_ident(input))
+}
+
+fn missing_variant(ident: &Ident) -> Ident {
+    format_ident!("Missing{}", to_camel_case(ident))
+}
+
+fn invalid_variant(ident: &Ident) -> Ident {
+    format_ident!("Invalid{}", to_camel_case(ident))
+}
+
+fn unresolved_ident(ident: &Ident) -> Ident {
+    format_ident!("__builder_{}", ident.unraw())
+}
+
+fn state_params(input: &Input) -> Vec<Ident> {
+    if !input.options.typestate {
+        return vec![];
+    }
+
+    input
+        .fields
+        .iter()
+        .filter(|field| field.is_mandatory())
+        .map(|field| state_param(&field.ident))
+        .collect()
+}
+
+fn state_param(ident: &Ident) -> Ident {
+    format_ident!("__{}", to_camel_case(ident))
+}
+
+fn builder_generics(input: &Input) -> Generics {
+    let mut generics = input.generics.clone();
+    for param in state_params(input) {
+        generics.params.push(GenericParam::Type(param.into()));
+    }
+
+    generics
+}
+
+fn marker_field(input: &Input) -> Option<(TokenStream, TokenStream)> {
+    let lifetimes: Vec<_> = input
+        .generics
+        .lifetimes()
+        .map(|param| &param.lifetime)
+        .collect();
+    let types: Vec<_> = input
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+
+    if lifetimes.is_empty() && types.is_empty() {
+        return None;
+    }
+
+    Some((
+        quote! { __phantom: ::core::marker::PhantomData<fn() -> (#(&#lifetimes (),)* #(#types,)*)> },
+        quote! { __phantom: ::core::marker::PhantomData },
+    ))
+}
+
+fn builder_ty<I, T>(input: &Input, states: I) -> TokenStream
+where
+    I: IntoIterator<Item = T>,
+    T: ToTokens,
+{
+    let builder_ident = builder_ident(input);
+    let args = input.generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(param) => param.lifetime.to_token_stream(),
+        GenericParam::Type(param) => param.ident.to_token_stream(),
+        GenericParam::Const(param) => param.ident.to_token_stream(),
+    });
+    let states = states.into_iter();
+
+    quote! { #builder_ident<#(#args,)* #(#states),*> }
+}
+
+fn complete_builder_ty(input: &Input) -> TokenStream {
+    let states = input
+        .fields
+        .iter()
+        .filter(|field| input.options.typestate && field.is_mandatory())
+        .map(|field| field.ty);
+
+    builder_ty(input, states)
+}
+
+fn alloc_crate(input: &Input) -> TokenStream {
+    if input.options.no_std {
+        quote! { ::alloc }
+    } else {
+        quote! { ::std }
+    }
+}
+
+fn setter_arg(into: bool, arg: &Ident, ty: &Type) -> (TokenStream, TokenStream) {
+    if into {
+        (
+            quote! { impl ::core::convert::Into<#ty> },
+            quote! { ::core::convert::Into::into(#arg) },
+        )
+    } else {
+        (quote! { #ty }, quote! { #arg })
+    }
 }
 
 pub(crate) fn output_builder_type(input: &Input) -> TokenStream {
-    let optional_fields = input.fields.iter().map(|field| match *field {
-        BuilderField::Mandatory { ident, ty } | BuilderField::Optional { ident, ty } => {
-            quote! { #ident: ::std::option::Option<#ty> }
-        }
-        BuilderField::Multi { ident, ty, .. } => {
-            quote! { #ident: #ty }
+    let vis = input.builder_vis();
+    let typestate = input.options.typestate;
+    let mut params = state_params(input).into_iter();
+
+    let optional_fields = input.fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        match field.kind {
+            FieldKind::Mandatory if typestate => {
+                let param = params.next();
+                Some(quote! { #ident: #param })
+            }
+            FieldKind::Mandatory | FieldKind::Optional { .. } | FieldKind::Defaulted { .. } => {
+                let ty = field.setter_ty();
+                Some(quote! { #ident: ::core::option::Option<#ty> })
+            }
+            FieldKind::Multi { .. } => {
+                let ty = field.ty;
+                Some(quote! { #ident: #ty })
+            }
+            FieldKind::Skipped { .. } => None,
         }
     });
 
+    let error_ident = error_ident(input);
+    let error_slots = input
+        .fields
+        .iter()
+        .filter_map(|field| field.error_slot())
+        .map(|slot| quote! { #slot: ::core::option::Option<#error_ident> });
+    let marker = marker_field(input).map(|(decl, _)| decl);
+    let mut derives: Vec<_> = input
+        .options
+        .derives
+        .iter()
+        .map(ToTokens::to_token_stream)
+        .collect();
+    let derives_clone = input.options.derives.iter().any(|path| {
+        path.segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Clone")
+    });
+    if input.options.pattern == Pattern::Immutable && !typestate && !derives_clone {
+        derives.insert(0, quote! { ::core::clone::Clone });
+    }
+    let derive = (!derives.is_empty()).then(|| quote! { #[derive(#(#derives),*)] });
+    let attrs = &input.options.attrs;
+
     let builder_ident = builder_ident(input);
+    let generics = builder_generics(input);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
 
     quote! {
-        pub struct #builder_ident {
-            #(#optional_fields),*
+        #derive
+        #(#[#attrs])*
+        #vis struct #builder_ident #impl_generics #where_clause {
+            #(#optional_fields,)*
+            #(#error_slots,)*
+            #marker
         }
     }
-    .into()
 }
 
 pub(crate) fn output_builder_constructor(input: &Input) -> TokenStream {
-    let field_initializers = input.fields.iter().map(|field| match *field {
-        BuilderField::Mandatory { ident, .. } | BuilderField::Optional { ident, .. } => {
-            quote! { #ident: ::std::option::Option::None }
-        }
-        BuilderField::Multi { ident, .. } => {
-            quote! { #ident: ::core::default::Default::default() }
+    let vis = input.builder_vis();
+    let typestate = input.options.typestate;
+
+    let field_initializers = input.fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        match field.kind {
+            FieldKind::Mandatory if typestate => Some(quote! { #ident: () }),
+            FieldKind::Mandatory | FieldKind::Defaulted { .. } => {
+                Some(quote! { #ident: ::core::option::Option::None })
+            }
+            FieldKind::Optional { ref inner_ty } => {
+                let ty = field.ty;
+                Some(quote_spanned! { ty.span() =>
+                    #ident: {
+                        let _: #ty = ::core::option::Option::<#inner_ty>::None;
+                        ::core::option::Option::None
+                    }
+                })
+            }
+            FieldKind::Multi { ref elem, .. } => {
+                let ty = field.ty;
+                let extend = extend_collection(
+                    field,
+                    elem,
+                    quote! { &mut collection },
+                    quote! { ::core::option::Option::None },
+                );
+                Some(quote_spanned! { ty.span() =>
+                    #ident: {
+                        let mut collection: #ty = ::core::default::Default::default();
+                        #extend;
+                        collection
+                    }
+                })
+            }
+            FieldKind::Skipped { .. } => None,
         }
     });
 
+    let error_slots = input.fields.iter().filter_map(|field| field.error_slot());
+    let marker = marker_field(input).map(|(_, init)| init);
+
+    let extend_collection = input
+        .fields
+        .iter()
+        .any(|field| matches!(field.kind, FieldKind::Multi { .. }))
+        .then(|| {
+            let generics = builder_generics(input);
+            let (impl_generics, _, where_clause) = generics.split_for_impl();
+            let self_ty = builder_ty(input, state_params(input));
+            quote! {
+                impl #impl_generics #self_ty #where_clause {
+                    fn __extend_collection<C, I>(
+                        collection: &mut C,
+                        items: impl ::core::iter::IntoIterator<Item = I>,
+                    ) -> bool
+                    where
+                        C: ::core::default::Default + ::core::iter::Extend<I>,
+                    {
+                        let mut extended = false;
+                        collection.extend(items.into_iter().inspect(|_| extended = true));
+                        extended
+                    }
+                }
+            }
+        });
+
     let ident = input.ident;
+    let constructor_ident = constructor_ident(input);
     let builder_ident = builder_ident(input);
+    let builder_ty = builder_ty(input, state_params(input).iter().map(|_| quote! { () }));
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
     quote! {
-        impl #ident {
-             pub fn builder() -> #builder_ident {
+        impl #impl_generics ::core::default::Default for #builder_ty #where_clause {
+            fn default() -> Self {
                 #builder_ident {
-                    #(#field_initializers),*
+                    #(#field_initializers,)*
+                    #(#error_slots: ::core::option::Option::None,)*
+                    #marker
                 }
             }
         }
+
+        impl #impl_generics #builder_ty #where_clause {
+            #vis fn new() -> Self {
+                ::core::default::Default::default()
+            }
+        }
+
+        #extend_collection
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #vis fn #constructor_ident() -> #builder_ty {
+                ::core::default::Default::default()
+            }
+        }
     }
-    .into()
 }
 
 pub(crate) fn output_setters(input: &Input) -> TokenStream {
     let builder_ident = builder_ident(input);
+    let params = state_params(input);
+    let generics = builder_generics(input);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let self_ty = builder_ty(input, &params);
+
+    let field_setters = input.fields.iter().flat_map(|field| {
+        let ident = &field.ident;
+        let setter_ident = field.setter_ident();
+        match field.kind {
+            FieldKind::Mandatory if input.options.typestate => {
+                let (arg_ty, value) = setter_arg(field.setter.into, ident, field.ty);
+                let new_ty = typestate_setter_ty(input, &params, field);
+                let other_fields = input
+                    .fields
+                    .iter()
+                    .filter(|other| !other.is_skipped())
+                    .map(|other| &other.ident)
+                    .filter(|other| *other != ident);
+                let marker = marker_field(input).map(|(_, init)| init);
+                let setter_vis = field.setter_vis(input);
+
+                Some(quote! {
+                    #setter_vis fn #setter_ident(self, #ident: #arg_ty) -> #new_ty {
+                        #builder_ident {
+                            #ident: #value,
+                            #(#other_fields: self.#other_fields,)*
+                            #marker
+                        }
+                    }
+                })
+            }
+            FieldKind::Mandatory | FieldKind::Optional { .. } | FieldKind::Defaulted { .. }
+                if field.setter.parse.is_some() =>
+            {
+                let ty = field.setter_ty();
+                let slot = field.error_slot();
+                let alloc = alloc_crate(input);
+                let error_ident = error_ident(input);
+                let variant = invalid_variant(ident);
+                Some(output_setter(
+                    input,
+                    field,
+                    &setter_ident,
+                    quote! { #ident: &str },
+                    |this| {
+                        quote! {
+                            match <#ty as ::core::str::FromStr>::from_str(#ident) {
+                                ::core::result::Result::Ok(value) => {
+                                    #this.#ident = ::core::option::Option::Some(value);
+                                }
+                                ::core::result::Result::Err(err) => {
+                                    #this.#slot = ::core::option::Option::Some(#error_ident::#variant(
+                                        #alloc::string::ToString::to_string(&err),
+                                    ));
+                                }
+                            }
+                        }
+                    },
+                ))
+            }
+            FieldKind::Optional { .. } if !field.setter.strips_option() => {
+                let (arg_ty, value) = setter_arg(field.setter.into, ident, field.ty);
+                Some(output_setter(
+                    input,
+                    field,
+                    &setter_ident,
+                    quote! { #ident: #arg_ty },
+                    |this| quote! { #this.#ident = #value; },
+                ))
+            }
+            FieldKind::Mandatory | FieldKind::Optional { .. } | FieldKind::Defaulted { .. } => {
+                let (arg_ty, value) = setter_arg(field.setter.into, ident, field.setter_ty());
+                Some(output_setter(
+                    input,
+                    field,
+                    &setter_ident,
+                    quote! { #ident: #arg_ty },
+                    |this| quote! { #this.#ident = ::core::option::Option::Some(#value); },
+                ))
+            }
+            FieldKind::Multi { with_set_all, .. } => {
+                let (arg_ty, value) = setter_arg(field.setter.into, ident, field.ty);
+                with_set_all.then(|| {
+                    output_setter(
+                        input,
+                        field,
+                        &setter_ident,
+                        quote! { #ident: #arg_ty },
+                        |this| quote! { #this.#ident = #value; },
+                    )
+                })
+            }
+            FieldKind::Skipped { .. } => None,
+        }
+    });
+
+    let try_setters = input
+        .fields
+        .iter()
+        .filter(|field| field.setter.try_into)
+        .filter_map(|field| {
+            let ident = &field.ident;
+            let setter_ident = field.setter_ident();
+            let try_ident = field.companion_ident("try");
+            let vis = field.setter_vis(input);
+            let target_ty = match field.kind {
+                FieldKind::Mandatory if input.options.typestate => field.ty,
+                FieldKind::Optional { .. } if !field.setter.strips_option() => field.ty,
+                FieldKind::Mandatory | FieldKind::Optional { .. } | FieldKind::Defaulted { .. } => {
+                    field.setter_ty()
+                }
+                FieldKind::Multi { .. } | FieldKind::Skipped { .. } => return None,
+            };
+            let (receiver, ret_ty, clone_bound) = if field.is_mandatory() && input.options.typestate
+            {
+                let new_ty = typestate_setter_ty(input, &params, field);
+                (quote! { self }, new_ty, None)
+            } else {
+                match input.options.pattern {
+                    _ if input.options.typestate => (quote! { self }, quote! { Self }, None),
+                    Pattern::Mutable => (quote! { &mut self }, quote! { &mut Self }, None),
+                    Pattern::Owned => (quote! { self }, quote! { Self }, None),
+                    Pattern::Immutable => (
+                        quote! { &self },
+                        quote! { Self },
+                        Some(quote! { Self: ::core::clone::Clone, }),
+                    ),
+                }
+            };
 
-    let field_setters = input.fields.iter().flat_map(|field| match *field {
-        BuilderField::Mandatory { ident, ty } | BuilderField::Optional { ident, ty } => {
             Some(quote! {
-                fn #ident(&mut self, #ident: #ty) -> &mut Self {
-                    self.#ident = ::std::option::Option::Some(#ident);
-                    self
+                #vis fn #try_ident<__TryValue>(
+                    #receiver,
+                    #ident: __TryValue,
+                ) -> ::core::result::Result<#ret_ty, __TryValue::Error>
+                where
+                    __TryValue: ::core::convert::TryInto<#target_ty>,
+                    #clone_bound
+                {
+                    ::core::result::Result::Ok(
+                        self.#setter_ident(::core::convert::TryInto::try_into(#ident)?),
+                    )
                 }
             })
+        });
+
+    let optional_setters = input.fields.iter().flat_map(|field| match field.kind {
+        FieldKind::Optional { ref inner_ty } => {
+            let ident = &field.ident;
+            let maybe = field.setter.strips_option().then(|| {
+                output_setter(
+                    input,
+                    field,
+                    &field.companion_ident("maybe"),
+                    quote! { #ident: ::core::option::Option<#inner_ty> },
+                    |this| quote! { #this.#ident = #ident; },
+                )
+            });
+            let unset = output_setter(
+                input,
+                field,
+                &field.companion_ident("unset"),
+                quote! {},
+                |this| quote! { #this.#ident = ::core::option::Option::None; },
+            );
+
+            maybe.into_iter().chain([unset]).collect()
         }
-        BuilderField::Multi {
-            ident,
-            ty,
-            with_set_all,
-            ..
-        } => with_set_all.then_some(quote! {
-            fn #ident(&mut self, #ident: #ty) -> &mut Self {
-                self.#ident = #ident;
-                self
-            }
-        }),
+        _ => vec![],
     });
 
-    let multi_setters = input.fields.iter().flat_map(|field| match *field {
-        BuilderField::Multi {
-            ident,
-            elem_ty,
-            ref attrs,
-            ..
-        } => attrs
-            .iter()
-            .map(|attr| {
-                quote! {
-                    fn #attr(&mut self, #attr: #elem_ty) -> &mut Self {
-                        self.#ident.push(#attr);
-                        self
-                    }
-                }
-            })
-            .collect(),
+    let multi_setters = input.fields.iter().flat_map(|field| match field.kind {
+        FieldKind::Multi {
+            ref elem, ref each, ..
+        } => {
+            let ident = &field.ident;
+            let ty = field.ty;
+            let span = ty.span();
+            let item_ty = elem_item_ty(elem);
+            let mut extend_ident = field.companion_ident("extend");
+            extend_ident.set_span(span);
+            let extend = output_setter(
+                input,
+                field,
+                &extend_ident,
+                quote_spanned! { span => items: impl ::core::iter::IntoIterator<Item = #item_ty> },
+                |this| {
+                    let extend =
+                        extend_collection(field, elem, quote! { &mut #this.#ident }, quote! { items });
+                    quote! { #extend; }
+                },
+            );
+            let clear = output_setter(
+                input,
+                field,
+                &field.companion_ident("clear"),
+                quote! {},
+                |this| quote_spanned! { span => #this.#ident = ::core::default::Default::default(); },
+            );
+
+            each.iter()
+                .map(|each| {
+                    let name = &field.prefixed(&each.ident);
+                    let into = each.into || field.setter.into;
+                    let (args, item) = match elem {
+                        Elem::Item(elem_ty) => {
+                            let (arg_ty, value) = setter_arg(into, name, elem_ty);
+                            (quote! { #name: #arg_ty }, value)
+                        }
+                        Elem::Entry(key_ty, value_ty) => {
+                            let (key_arg_ty, key) = setter_arg(into, &format_ident!("key"), key_ty);
+                            let (value_arg_ty, value) =
+                                setter_arg(into, &format_ident!("value"), value_ty);
+                            (
+                                quote! { key: #key_arg_ty, value: #value_arg_ty },
+                                quote! { (#key, #value) },
+                            )
+                        }
+                    };
+                    output_setter(input, field, name, args, |this| {
+                        let extend = extend_collection(
+                            field,
+                            elem,
+                            quote! { &mut #this.#ident },
+                            quote! { ::core::option::Option::Some(#item) },
+                        );
+                        quote! { #extend; }
+                    })
+                })
+                .chain([extend, clear])
+                .collect()
+        }
         _ => vec![],
     });
 
     quote! {
-        impl #builder_ident {
+        impl #impl_generics #self_ty #where_clause {
             #(#field_setters)*
+            #(#try_setters)*
+            #(#optional_setters)*
             #(#multi_setters)*
         }
     }
-    .into()
+}
+
+fn typestate_setter_ty(input: &Input, params: &[Ident], field: &BuilderField) -> TokenStream {
+    let ty = field.ty;
+    let own_param = state_param(&field.ident);
+
+    builder_ty(
+        input,
+        params.iter().map(|param| {
+            if *param == own_param {
+                quote! { #ty }
+            } else {
+                quote! { #param }
+            }
+        }),
+    )
+}
+
+fn output_setter<F>(
+    input: &Input,
+    field: &BuilderField,
+    name: &Ident,
+    args: TokenStream,
+    update: F,
+) -> TokenStream
+where
+    F: FnOnce(&TokenStream) -> TokenStream,
+{
+    let slot = field.error_slot();
+    output_builder_method(input, field.setter_vis(input), name, args, |this| {
+        let clear_error = slot.map(|slot| quote! { #this.#slot = ::core::option::Option::None; });
+        let update = update(this);
+        quote! {
+            #clear_error
+            #update
+        }
+    })
+}
+
+fn output_builder_method<F>(
+    input: &Input,
+    vis: &Visibility,
+    name: &Ident,
+    args: TokenStream,
+    update: F,
+) -> TokenStream
+where
+    F: FnOnce(&TokenStream) -> TokenStream,
+{
+    let pattern = if input.options.typestate {
+        Pattern::Owned
+    } else {
+        input.options.pattern
+    };
+
+    let span = name.span();
+    let vis = respan(vis.to_token_stream(), span);
+    match pattern {
+        Pattern::Mutable => {
+            let update = update(&quote! { self });
+            quote_spanned! { span =>
+                #vis fn #name(&mut self, #args) -> &mut Self {
+                    #update
+                    self
+                }
+            }
+        }
+        Pattern::Owned => {
+            let update = update(&quote! { self });
+            quote_spanned! { span =>
+                #vis fn #name(mut self, #args) -> Self {
+                    #update
+                    self
+                }
+            }
+        }
+        Pattern::Immutable => {
+            let update = update(&quote! { __builder });
+            quote_spanned! { span =>
+                #vis fn #name(&self, #args) -> Self
+                where
+                    Self: ::core::clone::Clone,
+                {
+                    let mut __builder = ::core::clone::Clone::clone(self);
+                    #update
+                    __builder
+                }
+            }
+        }
+    }
 }
 
 pub(crate) fn output_build_method(input: &Input) -> TokenStream {
-    let build_fields = input.fields.iter().map(|field| match *field {
-        BuilderField::Optional { ident, .. } | BuilderField::Multi { ident, .. } => quote! {
-            #ident: ::std::mem::take(&mut self.#ident)
-        },
-        BuilderField::Mandatory { ident, .. } => {
-            let msg = format!("{} not set", ident);
+    let typestate = input.options.typestate;
+    let pattern = if typestate {
+        Pattern::Owned
+    } else {
+        input.options.pattern
+    };
+    let clone = pattern == Pattern::Immutable || input.options.build_fn.clone;
+    let error_ident = error_ident(input);
+    let alloc = alloc_crate(input);
+
+    let checks = input.fields.iter().map(|field| {
+        let ident = &field.ident;
+        let stored_raw =
+            matches!(field.kind, FieldKind::Multi { .. }) || (typestate && field.is_mandatory());
+
+        let missing = (field.is_mandatory() && !typestate).then(|| {
+            let variant = missing_variant(ident);
+            quote! {
+                if self.#ident.is_none() {
+                    errors.push(#error_ident::#variant);
+                }
+            }
+        });
+
+        let validation = field.validate.as_ref().map(|validate| {
+            let variant = invalid_variant(ident);
+            let check = quote! {
+                if let ::core::result::Result::Err(err) = (#validate)(value) {
+                    errors.push(#error_ident::#variant(#alloc::string::ToString::to_string(&err)));
+                }
+            };
+
+            if stored_raw {
+                quote! {
+                    {
+                        let value = &self.#ident;
+                        #check
+                    }
+                }
+            } else {
+                quote! {
+                    if let ::core::option::Option::Some(ref value) = self.#ident {
+                        #check
+                    }
+                }
+            }
+        });
+
+        match field.error_slot() {
+            Some(slot) => {
+                quote! {
+                    if let ::core::option::Option::Some(ref err) = self.#slot {
+                        errors.push(::core::clone::Clone::clone(err));
+                    } else {
+                        #missing
+                        #validation
+                    }
+                }
+            }
+            None => quote! {
+                #missing
+                #validation
+            },
+        }
+    });
+
+    let field_values = input
+        .fields
+        .iter()
+        .filter(|field| !field.is_skipped())
+        .map(|field| {
+            let ident = &field.ident;
+            let value = match pattern {
+                _ if clone => quote! { ::core::clone::Clone::clone(&self.#ident) },
+                Pattern::Mutable => match field.kind {
+                    FieldKind::Optional { .. } | FieldKind::Multi { .. } => {
+                        quote! { ::core::mem::take(&mut self.#ident) }
+                    }
+                    FieldKind::Mandatory
+                    | FieldKind::Defaulted { .. }
+                    | FieldKind::Skipped { .. } => {
+                        quote! { self.#ident.take() }
+                    }
+                },
+                Pattern::Owned | Pattern::Immutable => quote! { self.#ident },
+            };
+
+            match field.kind {
+                FieldKind::Defaulted { .. } => {
+                    let unresolved = unresolved_ident(ident);
+                    quote! { let #unresolved = #value; }
+                }
+                _ if field.is_mandatory() && !typestate => quote! { let #ident = #value.unwrap(); },
+                _ => quote! { let #ident = #value; },
+            }
+        });
+    let build_ident = input
+        .options
+        .build_fn
+        .name
+        .clone()
+        .unwrap_or_else(|| format_ident!("build"));
+    let receiver = match pattern {
+        _ if clone => quote! { &self },
+        Pattern::Mutable => quote! { &mut self },
+        Pattern::Owned | Pattern::Immutable => quote! { self },
+    };
+    let clone_bounds = clone_bounds(input, clone);
+    let defaults = output_defaults(input);
+    let members = input.fields.iter().map(|field| &field.member);
+    let build_fields = input.fields.iter().map(|field| {
+        let mut ident = field.ident.clone();
+        if let FieldKind::Optional { .. } = field.kind {
+            ident.set_span(field.ty.span());
+        }
+        ident
+    });
+
+    let ident = input.ident;
+    let target = input.target();
+    let vis = input.builder_vis();
+    let builder_ty = complete_builder_ty(input);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let restore = (pattern == Pattern::Mutable && !clone && !typestate)
+        .then(|| output_restore(input))
+        .flatten();
+    let unset_flags = restore.as_ref().map(|(flags, _)| flags);
+
+    let build = quote! {
+        #(#field_values)*
+        #unset_flags
+        #defaults
+
+        let value = #target {
+            #(#members: #build_fields),*
+        };
+    };
+
+    let body = if is_fallible(input) {
+        let validate = input.options.build_fn.validate.as_ref().map(|validate| {
+            let restore = restore.as_ref().map(|(_, restore)| restore);
             quote! {
-                #ident: self.#ident.take().ok_or(#msg)?
+                if let ::core::result::Result::Err(err) = (#validate)(&value) {
+                    #restore
+                    return ::core::result::Result::Err(
+                        #error_ident::Validation(#alloc::string::ToString::to_string(&err)),
+                    );
+                }
+            }
+        });
+
+        quote! {
+            -> ::core::result::Result<#ident #ty_generics, #error_ident>
+            where
+                #(#clone_bounds,)*
+            {
+                let mut errors = #alloc::vec::Vec::new();
+                #(#checks)*
+                #error_ident::from_errors(errors)?;
+
+                #build
+                #validate
+
+                ::core::result::Result::Ok(value)
+            }
+        }
+    } else {
+        quote! {
+            -> #ident #ty_generics
+            where
+                #(#clone_bounds,)*
+            {
+                #build
+                value
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics #builder_ty #where_clause {
+            #vis fn #build_ident(#receiver) #body
+        }
+    }
+}
+
+fn output_restore(input: &Input) -> Option<(TokenStream, TokenStream)> {
+    input.options.build_fn.validate.as_ref()?;
+
+    let fields: Vec<_> = input
+        .fields
+        .iter()
+        .filter(|field| !field.is_skipped())
+        .collect();
+    let unset_flag = |field: &BuilderField| format_ident!("__{}_unset", field.ident.unraw());
+    let unset_flags = fields.iter().filter_map(|field| match field.kind {
+        FieldKind::Defaulted { .. } => {
+            let unresolved = unresolved_ident(&field.ident);
+            let flag = unset_flag(field);
+            Some(quote! { let #flag = #unresolved.is_none(); })
+        }
+        _ => None,
+    });
+    let members = fields.iter().map(|field| &field.member);
+    let idents = fields.iter().map(|field| &field.ident);
+    let restores = fields.iter().map(|field| {
+        let ident = &field.ident;
+        match field.kind {
+            FieldKind::Mandatory => quote! { self.#ident = ::core::option::Option::Some(#ident); },
+            FieldKind::Defaulted { .. } => {
+                let flag = unset_flag(field);
+                quote! {
+                    if !#flag {
+                        self.#ident = ::core::option::Option::Some(#ident);
+                    }
+                }
+            }
+            FieldKind::Optional { .. } | FieldKind::Multi { .. } | FieldKind::Skipped { .. } => {
+                quote! { self.#ident = #ident; }
+            }
+        }
+    });
+    let target = input.target();
+
+    let restore = quote! {
+        match value {
+            #[allow(non_shorthand_field_patterns)]
+            #target { #(#members: #idents,)* .. } => {
+                #(#restores)*
+            }
+            #[allow(unreachable_patterns)]
+            _ => {}
+        }
+    };
+
+    Some((quote! { #(#unset_flags)* }, restore))
+}
+
+fn extend_collection(
+    field: &BuilderField,
+    elem: &Elem,
+    collection: TokenStream,
+    items: TokenStream,
+) -> TokenStream {
+    let ty = field.ty;
+    let item_ty = elem_item_ty(elem);
+    quote_spanned! { ty.span() =>
+        Self::__extend_collection::<#ty, #item_ty>(#collection, #items)
+    }
+}
+
+fn elem_item_ty(elem: &Elem) -> TokenStream {
+    match elem {
+        Elem::Item(elem_ty) => quote! { #elem_ty },
+        Elem::Entry(key_ty, value_ty) => quote! { (#key_ty, #value_ty) },
+    }
+}
+
+fn is_fallible(input: &Input) -> bool {
+    !input.options.typestate
+        || input.options.build_fn.validate.is_some()
+        || input.fields.iter().any(|field| field.can_be_invalid())
+}
+
+fn clone_bounds(input: &Input, clone: bool) -> Vec<TokenStream> {
+    if !clone {
+        return vec![];
+    }
+
+    input
+        .fields
+        .iter()
+        .filter(|field| !field.is_skipped())
+        .map(|field| {
+            let ty = field.ty;
+            quote! { #ty: ::core::clone::Clone }
+        })
+        .collect()
+}
+
+fn output_defaults(input: &Input) -> TokenStream {
+    let default_expr = |default: &Option<Box<Expr>>| match default {
+        Some(expr) => quote! { #expr },
+        None => quote! { ::core::default::Default::default() },
+    };
+
+    let defaults = input.fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        match field.kind {
+            FieldKind::Defaulted { ref default } => {
+                let unresolved = unresolved_ident(ident);
+                let default = default_expr(default);
+                Some(quote! {
+                    let #ident = match #unresolved {
+                        ::core::option::Option::Some(value) => value,
+                        ::core::option::Option::None => #default,
+                    };
+                })
+            }
+            FieldKind::Skipped { ref default } => {
+                let default = default_expr(default);
+                Some(quote! { let #ident = #default; })
+            }
+            _ => None,
+        }
+    });
+
+    quote! { #(#defaults)* }
+}
+
+pub(crate) fn output_error_type(input: &Input) -> TokenStream {
+    if !is_fallible(input) {
+        return TokenStream::new();
+    }
+
+    let mandatory_fields = input
+        .fields
+        .iter()
+        .filter(|field| field.is_mandatory() && !input.options.typestate)
+        .map(|field| &field.ident)
+        .collect::<Vec<_>>();
+    let validated_fields = input
+        .fields
+        .iter()
+        .filter(|field| field.can_be_invalid())
+        .map(|field| &field.ident)
+        .collect::<Vec<_>>();
+
+    let missing_variants = mandatory_fields
+        .iter()
+        .map(|ident| missing_variant(ident))
+        .collect::<Vec<_>>();
+    let missing_messages = mandatory_fields
+        .iter()
+        .map(|ident| format!("{} not set", ident.unraw()));
+    let invalid_variants = validated_fields
+        .iter()
+        .map(|ident| invalid_variant(ident))
+        .collect::<Vec<_>>();
+    let invalid_prefixes = validated_fields
+        .iter()
+        .map(|ident| format!("invalid {}: ", ident.unraw()));
+
+    let error_ident = error_ident(input);
+    let has_env = input.fields.iter().any(|field| field.env.is_some());
+    let env_variant = has_env.then(|| {
+        quote! {
+            Env {
+                var: &'static str,
+                message: ::std::string::String,
+            },
+        }
+    });
+    let env_message = has_env.then(|| {
+        quote! {
+            #error_ident::Env { var, message } => {
+                f.write_str("invalid environment variable ")?;
+                f.write_str(var)?;
+                f.write_str(": ")?;
+                f.write_str(message)
+            }
+        }
+    });
+
+    let alloc = alloc_crate(input);
+    let has_validate = input.options.build_fn.validate.is_some();
+    let validation_variant = has_validate.then(|| quote! { Validation(#alloc::string::String), });
+    let validation_message = has_validate.then(|| {
+        quote! {
+            #error_ident::Validation(msg) => f.write_str(msg),
+        }
+    });
+
+    let vis = input.builder_vis();
+    let error_impl = (!input.options.no_std).then(|| {
+        quote! {
+            impl ::std::error::Error for #error_ident {}
+        }
+    });
+
+    quote! {
+        #[derive(::core::fmt::Debug, ::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+        #vis enum #error_ident {
+            #(#missing_variants,)*
+            #(#invalid_variants(#alloc::string::String),)*
+            #env_variant
+            #validation_variant
+            Multiple(#alloc::vec::Vec<#error_ident>),
+        }
+
+        impl #error_ident {
+            pub fn errors(&self) -> &[#error_ident] {
+                match self {
+                    #error_ident::Multiple(errors) => errors,
+                    _ => ::core::slice::from_ref(self),
+                }
+            }
+
+            fn from_errors(mut errors: #alloc::vec::Vec<#error_ident>) -> ::core::result::Result<(), #error_ident> {
+                match errors.len() {
+                    0 => ::core::result::Result::Ok(()),
+                    1 => ::core::result::Result::Err(errors.remove(0)),
+                    _ => ::core::result::Result::Err(#error_ident::Multiple(errors)),
+                }
+            }
+        }
+
+        impl ::core::fmt::Display for #error_ident {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#error_ident::#missing_variants => f.write_str(#missing_messages),)*
+                    #(#error_ident::#invalid_variants(msg) => {
+                        f.write_str(#invalid_prefixes)?;
+                        f.write_str(msg)
+                    })*
+                    #env_message
+                    #validation_message
+                    #error_ident::Multiple(errors) => {
+                        for (i, error) in errors.iter().enumerate() {
+                            if i > 0 {
+                                f.write_str(", ")?;
+                            }
+                            ::core::fmt::Display::fmt(error, f)?;
+                        }
+                        ::core::result::Result::Ok(())
+                    }
+                }
+            }
+        }
+
+        #error_impl
+    }
+}
+
+pub(crate) fn output_conversions(input: &Input) -> TokenStream {
+    if input.variant.is_some() {
+        return quote! {};
+    }
+
+    let typestate = input.options.typestate;
+    let slots = input.fields.iter().filter_map(|field| {
+        let ident = &field.ident;
+        let member = &field.member;
+        match field.kind {
+            FieldKind::Mandatory if typestate => Some(quote! { #ident: value.#member }),
+            FieldKind::Mandatory | FieldKind::Defaulted { .. } => {
+                Some(quote! { #ident: ::core::option::Option::Some(value.#member) })
+            }
+            FieldKind::Optional { .. } => {
+                let ty = field.ty;
+                Some(quote_spanned! { ty.span() =>
+                    #ident: {
+                        let value: #ty = value.#member;
+                        value
+                    }
+                })
             }
+            FieldKind::Multi { .. } => Some(quote! { #ident: value.#member }),
+            FieldKind::Skipped { .. } => None,
         }
     });
+    let error_slots = input.fields.iter().filter_map(|field| field.error_slot());
+    let marker = marker_field(input).map(|(_, init)| init);
 
     let ident = input.ident;
     let builder_ident = builder_ident(input);
+    let builder_ty = complete_builder_ty(input);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let to_builder = input.options.to_builder.map(|_| {
+        let vis = input.builder_vis();
+        quote! {
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #vis fn to_builder(&self) -> #builder_ty
+                where
+                    Self: ::core::clone::Clone,
+                {
+                    ::core::convert::From::from(::core::clone::Clone::clone(self))
+                }
+            }
+        }
+    });
 
     quote! {
-        impl #builder_ident {
-            pub fn build(&mut self) -> ::std::result::Result<#ident, ::std::boxed::Box<dyn ::std::error::Error>> {
-                ::std::result::Result::Ok(#ident {
-                    #(#build_fields),*
-                })
+        impl #impl_generics ::core::convert::From<#ident #ty_generics> for #builder_ty #where_clause {
+            fn from(value: #ident #ty_generics) -> Self {
+                #builder_ident {
+                    #(#slots,)*
+                    #(#error_slots: ::core::option::Option::None,)*
+                    #marker
+                }
             }
         }
 
+        #to_builder
+    }
+}
+
+pub(crate) fn output_merge(input: &Input) -> TokenStream {
+    if input.options.typestate {
+        return quote! {};
+    }
+
+    let vis = input.builder_vis();
+    let merge = output_builder_method(
+        input,
+        vis,
+        &format_ident!("merge"),
+        quote! { other: Self },
+        |this| {
+            let merges = input.fields.iter().map(|field| {
+                let ident = &field.ident;
+                let ty = field.ty;
+                let slot = field.error_slot();
+                let clear_error = slot
+                    .as_ref()
+                    .map(|slot| quote! { #this.#slot = ::core::option::Option::None; });
+                let other_items = quote_spanned! { ty.span() =>
+                    <#ty as ::core::iter::IntoIterator>::into_iter(other.#ident)
+                };
+                let merge = match field.kind {
+                    FieldKind::Mandatory
+                    | FieldKind::Optional { .. }
+                    | FieldKind::Defaulted { .. } => quote! {
+                        if other.#ident.is_some() {
+                            #this.#ident = other.#ident;
+                            #clear_error
+                        }
+                    },
+                    FieldKind::Multi {
+                        ref elem,
+                        merge: Merge::Append,
+                        ..
+                    } => {
+                        let extend = extend_collection(
+                            field,
+                            elem,
+                            quote! { &mut #this.#ident },
+                            other_items,
+                        );
+                        quote! { #extend; }
+                    }
+                    FieldKind::Multi {
+                        ref elem,
+                        merge: Merge::Replace,
+                        ..
+                    } => {
+                        let extend =
+                            extend_collection(field, elem, quote! { &mut items }, other_items);
+                        quote_spanned! { ty.span() =>
+                            let mut items: #ty = ::core::default::Default::default();
+                            if #extend {
+                                #this.#ident = items;
+                            }
+                        }
+                    }
+                    FieldKind::Skipped { .. } => quote! {},
+                };
+                let merge_error = slot.map(|slot| {
+                    quote! {
+                        if other.#slot.is_some() {
+                            #this.#slot = other.#slot;
+                        }
+                    }
+                });
+
+                quote! {
+                    {
+                        #merge
+                        #merge_error
+                    }
+                }
+            });
+
+            quote! { #(#merges)* }
+        },
+    );
+
+    let builder_ty = builder_ty(input, None::<TokenStream>);
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #builder_ty #where_clause {
+            #merge
+        }
+    }
+}
+
+pub(crate) fn output_env(input: &Input) -> TokenStream {
+    if input.fields.iter().all(|field| field.env.is_none()) {
+        return quote! {};
+    }
+
+    let error_ident = error_ident(input);
+    let env_error = |var: &LitStr, message: TokenStream| {
+        quote! {
+            ::core::option::Option::Some(#error_ident::Env {
+                var: #var,
+                message: #message,
+            })
+        }
+    };
+
+    let vis = input.builder_vis();
+    let fill = output_builder_method(
+        input,
+        vis,
+        &format_ident!("fill_from_env"),
+        quote! {},
+        |this| {
+            let fills = input.fields.iter().filter_map(|field| {
+                let env = field.env.as_ref()?;
+                let ident = &field.ident;
+                let slot = field.error_slot();
+                let var = &env.var;
+                let error = env_error(var, quote! { ::std::string::ToString::to_string(&err) });
+                let item_error = env_error(var, quote! { message });
+
+                let (unset, fill) = match field.kind {
+                    FieldKind::Multi { ref elem, .. } => {
+                        let extend = extend_collection(
+                            field,
+                            elem,
+                            quote! { &mut #this.#ident },
+                            quote! { items },
+                        );
+                        let separator = match env.separator {
+                            Some(ref separator) => quote! { #separator },
+                            None => quote! { "," },
+                        };
+                        let parse_item = match elem {
+                            Elem::Item(elem_ty) => quote! {
+                                <#elem_ty as ::core::str::FromStr>::from_str(item)
+                                    .map_err(|err| ::std::string::ToString::to_string(&err))
+                            },
+                            Elem::Entry(key_ty, value_ty) => quote! {
+                                match item.split_once('=') {
+                                    ::core::option::Option::Some((key, value)) => match (
+                                        <#key_ty as ::core::str::FromStr>::from_str(key),
+                                        <#value_ty as ::core::str::FromStr>::from_str(value),
+                                    ) {
+                                        (::core::result::Result::Ok(key), ::core::result::Result::Ok(value)) => {
+                                            ::core::result::Result::Ok((key, value))
+                                        }
+                                        (::core::result::Result::Err(err), _) => {
+                                            ::core::result::Result::Err(::std::string::ToString::to_string(&err))
+                                        }
+                                        (_, ::core::result::Result::Err(err)) => {
+                                            ::core::result::Result::Err(::std::string::ToString::to_string(&err))
+                                        }
+                                    },
+                                    ::core::option::Option::None => ::core::result::Result::Err(
+                                        ::std::string::String::from("expected `key=value`"),
+                                    ),
+                                }
+                            },
+                        };
+                        (
+                            quote! {
+                                ::core::iter::Iterator::next(
+                                    &mut ::core::iter::IntoIterator::into_iter(&#this.#ident),
+                                )
+                                .is_none()
+                            },
+                            quote! {
+                                let items = value
+                                    .split(#separator)
+                                    .filter(|item| !item.is_empty())
+                                    .map(|item| #parse_item)
+                                    .collect::<::core::result::Result<::std::vec::Vec<_>, ::std::string::String>>();
+                                match items {
+                                    ::core::result::Result::Ok(items) => {
+                                        #extend;
+                                    }
+                                    ::core::result::Result::Err(message) => {
+                                        #this.#slot = #item_error;
+                                    }
+                                }
+                            },
+                        )
+                    }
+                    FieldKind::Mandatory | FieldKind::Optional { .. } | FieldKind::Defaulted { .. } => {
+                        let ty = field.setter_ty();
+                        (
+                            quote! { #this.#ident.is_none() },
+                            quote! {
+                                match <#ty as ::core::str::FromStr>::from_str(&value) {
+                                    ::core::result::Result::Ok(value) => {
+                                        #this.#ident = ::core::option::Option::Some(value);
+                                    }
+                                    ::core::result::Result::Err(err) => {
+                                        #this.#slot = #error;
+                                    }
+                                }
+                            },
+                        )
+                    }
+                    FieldKind::Skipped { .. } => return None,
+                };
+
+                Some(quote! {
+                    if #this.#slot.is_none() && #unset {
+                        match ::std::env::var(#var) {
+                            ::core::result::Result::Ok(value) => {
+                                #fill
+                            }
+                            ::core::result::Result::Err(::std::env::VarError::NotPresent) => {}
+                            ::core::result::Result::Err(err) => {
+                                #this.#slot = #error;
+                            }
+                        }
+                    }
+                })
+            });
+
+            quote! { #(#fills)* }
+        },
+    );
+
+    let (from_env, clone_bound) = match input.options.pattern {
+        Pattern::Mutable => (
+            quote! {
+                let mut builder: Self = ::core::default::Default::default();
+                builder.fill_from_env();
+                builder
+            },
+            None,
+        ),
+        Pattern::Owned => (
+            quote! { <Self as ::core::default::Default>::default().fill_from_env() },
+            None,
+        ),
+        Pattern::Immutable => (
+            quote! { <Self as ::core::default::Default>::default().fill_from_env() },
+            Some(quote! { where Self: ::core::clone::Clone }),
+        ),
+    };
+
+    let builder_ty = builder_ty(input, None::<TokenStream>);
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #builder_ty #where_clause {
+            #vis fn from_env() -> Self #clone_bound {
+                #from_env
+            }
+
+            #fill
+        }
     }
-    .into()
 }
@@ -0,0 +1,19 @@
+// Forgetting a mandatory field of a typestate builder is a compile error
+// rather than a runtime one.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .args(vec![])
+        .current_dir("..".to_owned())
+        .build();
+}
@@ -0,0 +1,34 @@
+// The build method reports a missing mandatory field through a generated error
+// enum with one variant per mandatory field, so callers can match on which
+// field was not set instead of inspecting an error message. Variants for
+// features the struct does not use, such as `Validation`, are left out.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let err = Command::builder().arg("build".to_owned()).build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::MissingExecutable);
+    assert_eq!(err.to_string(), "executable not set");
+
+    match &err {
+        CommandBuilderError::MissingExecutable => {}
+        CommandBuilderError::Multiple(errors) => assert!(errors.len() > 1),
+    }
+
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    assert_eq!(err.to_string(), "executable not set");
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+}
@@ -0,0 +1,33 @@
+// When several mandatory fields are missing, build reports all of them at once
+// instead of stopping at the first one. The individual errors can be inspected
+// through `errors()`, which also works when only a single field is missing.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    let err = builder.current_dir("..".to_owned()).build().err().unwrap();
+    assert_eq!(
+        err.errors(),
+        [
+            CommandBuilderError::MissingExecutable,
+            CommandBuilderError::MissingArgs,
+        ]
+    );
+    assert_eq!(err.to_string(), "executable not set, args not set");
+
+    let err = builder.args(vec![]).build().err().unwrap();
+    assert_eq!(err, CommandBuilderError::MissingExecutable);
+    assert_eq!(err.errors(), [CommandBuilderError::MissingExecutable]);
+
+    let command = builder.executable("cargo".to_owned()).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+}
@@ -0,0 +1,87 @@
+// Fields marked #[builder(default)] no longer need to be set before calling
+// build. When left unset they are filled in with Default::default(), or with
+// the expression given as #[builder(default = "...")]. Such an expression may
+// refer to the values of the other fields by name.
+//
+// Defaults are resolved in field order, so an expression sees the final value
+// of the defaulted fields declared before it. Referring to a defaulted field
+// declared after it is a compile error. A field's own default, or a call
+// such as `retries()`, still reaches a function of the same name.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(default)]
+    env: Vec<String>,
+    #[builder(default = "format!(\"/home/{}\", executable)")]
+    current_dir: String,
+    #[builder(default = "3")]
+    retries: u32,
+    #[builder(default = "retries * 10")]
+    timeout: u32,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    name: String,
+    #[builder(default = "name.len()")]
+    priority: usize,
+}
+
+fn timeout() -> u32 {
+    60
+}
+
+fn retries() -> u32 {
+    5
+}
+
+#[derive(Builder)]
+pub struct Request {
+    #[builder(default = "timeout()")]
+    timeout: u32,
+    #[builder(default = "timeout * retries()")]
+    deadline: u32,
+    #[builder(default = "2")]
+    retries: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert!(command.args.is_empty());
+    assert!(command.env.is_empty());
+    assert_eq!(command.current_dir, "/home/cargo");
+    assert_eq!(command.retries, 3);
+    assert_eq!(command.timeout, 30);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .env(vec!["RUST_LOG=debug".to_owned()])
+        .current_dir("..".to_owned())
+        .retries(0)
+        .build()
+        .unwrap();
+    assert_eq!(command.env, vec!["RUST_LOG=debug"]);
+    assert_eq!(command.current_dir, "..");
+    assert_eq!(command.retries, 0);
+    assert_eq!(command.timeout, 0);
+
+    let job = Job::builder().name("compile".to_owned()).build();
+    assert_eq!(job.priority, 7);
+
+    let job = Job::builder().priority(1).name("compile".to_owned()).build();
+    assert_eq!(job.priority, 1);
+
+    let request = Request::builder().build().unwrap();
+    assert_eq!(request.timeout, 60);
+    assert_eq!(request.deadline, 300);
+    assert_eq!(request.retries, 2);
+}
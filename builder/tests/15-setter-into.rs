@@ -0,0 +1,58 @@
+// With #[builder(setter(into))] the generated setters accept anything that
+// converts into the field type, so callers can pass a &str where the field is a
+// String. The attribute works on individual fields as well as on the whole
+// struct, and also applies to the one-at-a-time setters of `each` fields.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg", setter(into))]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<PathBuf>,
+}
+
+#[derive(Builder)]
+#[builder(setter(into))]
+pub struct Job {
+    name: String,
+    working_dir: Option<PathBuf>,
+    #[builder(default)]
+    retries: u64,
+}
+
+#[derive(Builder)]
+#[builder(typestate, setter(into))]
+pub struct Task {
+    name: String,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .env(vec![])
+        .current_dir(PathBuf::from(".."))
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+
+    let job = Job::builder()
+        .name("compile")
+        .working_dir("/tmp")
+        .retries(3u8)
+        .build()
+        .unwrap();
+    assert_eq!(job.name, "compile");
+    assert_eq!(job.working_dir, Some(PathBuf::from("/tmp")));
+    assert_eq!(job.retries, 3);
+
+    let task = Task::builder().name("lint").build();
+    assert_eq!(task.name, "lint");
+}
@@ -0,0 +1,47 @@
+// The builder carries over the lifetimes, type parameters, const parameters and
+// where-clauses of the struct it is derived for.
+
+use derive_builder::Builder;
+use std::fmt::Debug;
+
+#[derive(Builder)]
+pub struct Request<'a, T: Clone, const N: usize, U = u8>
+where
+    U: Debug + Default,
+{
+    path: &'a str,
+    #[builder(each = "header")]
+    headers: Vec<(&'a str, T)>,
+    body: Option<[U; N]>,
+    #[builder(default)]
+    retries: U,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Pair<A, B: Default> {
+    first: A,
+    #[builder(default)]
+    second: B,
+}
+
+fn main() {
+    let path = String::from("/index.html");
+    let request = Request::<String, 2, u8>::builder()
+        .path(&path)
+        .header(("Accept", "text/html".to_owned()))
+        .body([1, 2])
+        .build()
+        .unwrap();
+    assert_eq!(request.path, "/index.html");
+    assert_eq!(request.headers, vec![("Accept", "text/html".to_owned())]);
+    assert_eq!(request.body, Some([1, 2]));
+    assert_eq!(request.retries, 0);
+
+    let err = Request::<String, 2, u8>::builder().build().err().unwrap();
+    assert_eq!(err, RequestBuilderError::MissingPath);
+
+    let pair: Pair<&str, u32> = Pair::builder().first("one").build();
+    assert_eq!(pair.first, "one");
+    assert_eq!(pair.second, 0);
+}
@@ -0,0 +1,36 @@
+// Tuple structs get positional setters named after the field index, `_0`,
+// `_1` and so on. A field can be given a more descriptive setter name with
+// #[builder(name = "...")].
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Point(i32, i32, #[builder(name = "label")] Option<String>);
+
+#[derive(Builder)]
+pub struct Port(#[builder(name = "number")] u16);
+
+fn main() {
+    let point = Point::builder()._0(1)._1(2).build().unwrap();
+    assert_eq!(point.0, 1);
+    assert_eq!(point.1, 2);
+    assert_eq!(point.2, None);
+
+    let point = Point::builder()
+        ._0(1)
+        ._1(2)
+        .label("origin".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(point.2.as_deref(), Some("origin"));
+
+    let err = Point::builder()._1(2).build().err().unwrap();
+    assert_eq!(err, PointBuilderError::Missing0);
+    assert_eq!(err.to_string(), "_0 not set");
+
+    let port = Port::builder().number(8080).build().unwrap();
+    assert_eq!(port.0, 8080);
+
+    let err = Port::builder().build().err().unwrap();
+    assert_eq!(err, PortBuilderError::MissingNumber);
+}
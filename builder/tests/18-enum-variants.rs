@@ -0,0 +1,56 @@
+// Deriving Builder on an enum generates one builder per variant with fields,
+// reachable through a constructor named after the variant. Unit variants do not
+// get a builder.
+
+use derive_builder::Builder;
+
+#[derive(Debug, PartialEq, Builder)]
+pub enum Shape {
+    Circle {
+        radius: f64,
+        label: Option<String>,
+    },
+    Rectangle {
+        width: f64,
+        height: f64,
+    },
+    RoundedRectangle(f64, f64, #[builder(name = "corner_radius")] f64),
+    Empty,
+}
+
+fn main() {
+    let circle = Shape::circle_builder().radius(1.5).build().unwrap();
+    assert_eq!(
+        circle,
+        Shape::Circle {
+            radius: 1.5,
+            label: None,
+        }
+    );
+
+    let rectangle = Shape::rectangle_builder()
+        .width(2.0)
+        .height(3.0)
+        .build()
+        .unwrap();
+    assert_eq!(
+        rectangle,
+        Shape::Rectangle {
+            width: 2.0,
+            height: 3.0,
+        }
+    );
+
+    let err = Shape::rectangle_builder().width(2.0).build().err().unwrap();
+    assert_eq!(err, ShapeRectangleBuilderError::MissingHeight);
+
+    let rounded = Shape::rounded_rectangle_builder()
+        ._0(2.0)
+        ._1(3.0)
+        .corner_radius(0.5)
+        .build()
+        .unwrap();
+    assert_eq!(rounded, Shape::RoundedRectangle(2.0, 3.0, 0.5));
+
+    let _ = Shape::Empty;
+}
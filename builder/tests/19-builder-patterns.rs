@@ -0,0 +1,81 @@
+// The style of the generated setters can be chosen with
+// #[builder(pattern = "...")]:
+//
+//   - "mutable" (the default) setters take `&mut self` and return `&mut Self`,
+//     and `build(&mut self)` moves the values out of the builder.
+//   - "owned" setters take `self` and return `Self`, and `build(self)`
+//     consumes the builder, so fields do not need to implement Clone or
+//     Default.
+//   - "immutable" setters take `&self` and return a modified copy of the
+//     builder, and `build(&self)` clones the values, so a builder can be kept
+//     around and reused. A field may still be called `builder`.
+
+use derive_builder::Builder;
+
+pub struct Handle {
+    id: u32,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Connection {
+    handle: Handle,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    timeout: Option<u64>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Image {
+    builder: String,
+    #[builder(each = "layer")]
+    layers: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Job {
+    name: String,
+}
+
+fn main() {
+    let connection = Connection::builder()
+        .handle(Handle { id: 7 })
+        .tag("primary".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(connection.handle.id, 7);
+    assert_eq!(connection.tags, vec!["primary"]);
+    assert_eq!(connection.timeout, None);
+
+    let err = Connection::builder().timeout(10).build().err().unwrap();
+    assert_eq!(err, ConnectionBuilderError::MissingHandle);
+
+    let base = Command::builder().executable("cargo".to_owned());
+    let build = base.arg("build".to_owned()).build().unwrap();
+    let test = base.arg("test".to_owned()).build().unwrap();
+    assert_eq!(build.args, vec!["build"]);
+    assert_eq!(test.args, vec!["test"]);
+    assert_eq!(base.build().unwrap().args, Vec::<String>::new());
+
+    let job = Job::builder().name("compile".to_owned()).build().unwrap();
+    assert_eq!(job.name, "compile");
+
+    let image = Image::builder()
+        .builder("buildah".to_owned())
+        .layer("base".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(image.builder, "buildah");
+    assert_eq!(image.layers, vec!["base"]);
+}
@@ -0,0 +1,44 @@
+// By default `build` moves the values out of a mutable builder, so a second
+// call reports the mandatory fields as missing. With
+// #[builder(build_fn(clone))] the build method takes `&self` and clones the
+// values instead, which lets one builder serve as a template for many structs.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(build_fn(clone))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate, build_fn(clone))]
+pub struct Job {
+    name: String,
+    priority: Option<u8>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+
+    let first = builder.build().unwrap();
+    let second = builder.current_dir("..".to_owned()).build().unwrap();
+    assert_eq!(first.executable, "cargo");
+    assert_eq!(first.args, vec!["build"]);
+    assert_eq!(first.current_dir, None);
+    assert_eq!(second.executable, "cargo");
+    assert_eq!(second.args, vec!["build"]);
+    assert_eq!(second.current_dir.as_deref(), Some(".."));
+
+    let template = Job::builder().name("compile".to_owned());
+    let low = template.build();
+    let high = template.priority(9).build();
+    assert_eq!(low.name, "compile");
+    assert_eq!(low.priority, None);
+    assert_eq!(high.name, "compile");
+    assert_eq!(high.priority, Some(9));
+}
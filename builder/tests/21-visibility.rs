@@ -0,0 +1,55 @@
+// The builder type, its constructor, setters and build method inherit the
+// visibility of the struct they are derived for, so a builder can be used from
+// other modules. The visibility can be overridden for the whole builder with
+// #[builder(vis = "...")], and for individual setters with
+// #[builder(vis = "...")] or #[builder(private)] on the field.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    pub struct Command {
+        pub executable: String,
+        #[builder(each = "arg")]
+        pub args: Vec<String>,
+        #[builder(private)]
+        pub(crate) token: Option<String>,
+    }
+
+    impl CommandBuilder {
+        pub fn authenticated(&mut self) -> &mut Self {
+            self.token("secret".to_owned())
+        }
+    }
+
+    #[derive(Builder)]
+    #[builder(vis = "pub(crate)")]
+    pub struct Job {
+        pub name: String,
+        #[builder(vis = "pub(super)")]
+        pub priority: Option<u8>,
+    }
+}
+
+use config::{Command, CommandBuilder, Job};
+
+fn main() {
+    let mut builder: CommandBuilder = Command::builder();
+    let command = builder
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .authenticated()
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.token.as_deref(), Some("secret"));
+
+    let job = Job::builder()
+        .name("compile".to_owned())
+        .priority(1)
+        .build()
+        .unwrap();
+    assert_eq!(job.name, "compile");
+    assert_eq!(job.priority, Some(1));
+}
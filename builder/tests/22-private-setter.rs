@@ -0,0 +1,20 @@
+// Setters marked #[builder(private)] are only callable from the module that
+// defines the struct.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    pub struct Command {
+        pub executable: String,
+        #[builder(private)]
+        pub token: Option<String>,
+    }
+}
+
+fn main() {
+    let _command = config::Command::builder()
+        .executable("cargo".to_owned())
+        .token("secret".to_owned())
+        .build();
+}
@@ -0,0 +1,112 @@
+// Validation hooks enforce invariants when building.
+//
+// A field-level #[builder(validate = "...")] names a function that receives a
+// reference to the field's value whenever it has been set. An error from it is
+// reported as an `Invalid<Field>` variant, together with any other invalid or
+// missing fields.
+//
+// A struct-level #[builder(build_fn(validate = "..."))] names a function that
+// receives the fully built struct and can check invariants spanning several
+// fields. Its error is reported as the `Validation` variant. A failed
+// validation leaves the builder untouched, so a field can be fixed and `build`
+// called again.
+
+use derive_builder::Builder;
+
+#[derive(Debug, Builder)]
+#[builder(build_fn(validate = "Range::check_range"))]
+pub struct Range {
+    #[builder(validate = "not_negative")]
+    start: i64,
+    end: i64,
+    #[builder(validate = "validate_name")]
+    name: Option<String>,
+    #[builder(each = "tag", validate = "few_tags")]
+    tags: Vec<String>,
+}
+
+impl Range {
+    fn check_range(&self) -> Result<(), String> {
+        if self.start > self.end {
+            return Err(format!("{} is after {}", self.start, self.end));
+        }
+        Ok(())
+    }
+}
+
+fn not_negative(value: &i64) -> Result<(), &'static str> {
+    if *value < 0 {
+        return Err("must not be negative");
+    }
+    Ok(())
+}
+
+fn validate_name(name: &String) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("must not be empty".to_owned());
+    }
+    Ok(())
+}
+
+fn few_tags(tags: &Vec<String>) -> Result<(), &'static str> {
+    if tags.len() > 2 {
+        return Err("at most two tags are allowed");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Builder)]
+#[builder(typestate, build_fn(validate = "|job: &Job| job.name.parse::<u32>().map(|_| ())"))]
+pub struct Job {
+    name: String,
+}
+
+fn main() {
+    let range = Range::builder().start(1).end(5).build().unwrap();
+    assert_eq!((range.start, range.end), (1, 5));
+
+    let err = Range::builder().start(-1).name(String::new()).build().unwrap_err();
+    assert_eq!(
+        err.errors(),
+        [
+            RangeBuilderError::InvalidStart("must not be negative".to_owned()),
+            RangeBuilderError::MissingEnd,
+            RangeBuilderError::InvalidName("must not be empty".to_owned()),
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid start: must not be negative, end not set, invalid name: must not be empty",
+    );
+
+    let err = Range::builder()
+        .start(0)
+        .end(1)
+        .tag("a".to_owned())
+        .tag("b".to_owned())
+        .tag("c".to_owned())
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RangeBuilderError::InvalidTags("at most two tags are allowed".to_owned())
+    );
+
+    let err = Range::builder().start(5).end(1).build().unwrap_err();
+    assert_eq!(err, RangeBuilderError::Validation("5 is after 1".to_owned()));
+
+    let mut builder = Range::builder();
+    builder.start(5).end(1).name("range".to_owned()).tag("a".to_owned());
+    let err = builder.build().unwrap_err();
+    assert_eq!(err, RangeBuilderError::Validation("5 is after 1".to_owned()));
+    let range = builder.end(9).build().unwrap();
+    assert_eq!((range.start, range.end), (5, 9));
+    assert_eq!(range.name.as_deref(), Some("range"));
+    assert_eq!(range.tags, ["a"]);
+
+    let job = Job::builder().name("42".to_owned()).build().unwrap();
+    assert_eq!(job.name, "42");
+
+    let err = Job::builder().name("x".to_owned()).build().unwrap_err();
+    assert_eq!(err.to_string(), "invalid digit found in string");
+}
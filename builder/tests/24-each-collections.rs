@@ -0,0 +1,63 @@
+// The #[builder(each = "...")] attribute is not limited to Vec. Any collection
+// that implements Default and Extend over its items can be filled one element
+// at a time. Maps get element setters taking a key and a value.
+//
+// The list form #[builder(each(name = "...", into))] additionally lets the
+// element setter accept anything convertible into the element type.
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+#[derive(Default, Debug, PartialEq)]
+pub struct Lines(Vec<String>);
+
+impl Extend<String> for Lines {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Lines {
+    type Item = String;
+    type IntoIter = std::vec::IntoIter<String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each(name = "arg", into))]
+    args: VecDeque<String>,
+    #[builder(each = "env")]
+    env: HashMap<String, String>,
+    #[builder(each(name = "label", into))]
+    labels: BTreeMap<String, String>,
+    #[builder(each = "tag")]
+    tags: HashSet<&'static str>,
+    #[builder(each = "line")]
+    script: Lines,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build")
+        .arg("--release")
+        .env("RUST_LOG".to_owned(), "debug".to_owned())
+        .label("team", "build")
+        .tag("ci")
+        .tag("ci")
+        .line("set -e".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env["RUST_LOG"], "debug");
+    assert_eq!(command.labels["team"], "build");
+    assert_eq!(command.tags.len(), 1);
+    assert_eq!(command.script, Lines(vec!["set -e".to_owned()]));
+}
@@ -0,0 +1,36 @@
+// Alongside the one-at-a-time setters, every `each` field gets an
+// `extend_<setter>` setter appending all items of an iterator, and a
+// `clear_<setter>` setter emptying the collection again.
+
+use derive_builder::Builder;
+use std::collections::BTreeMap;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    env: BTreeMap<String, String>,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder
+        .executable("cargo".to_owned())
+        .arg("test".to_owned())
+        .clear_args()
+        .arg("build".to_owned())
+        .extend_args(["--release", "--locked"].map(str::to_owned))
+        .extend_env(vec![("CI".to_owned(), "1".to_owned())])
+        .env("RUST_LOG".to_owned(), "debug".to_owned());
+
+    let command = builder.build().unwrap();
+    assert_eq!(command.args, ["build", "--release", "--locked"]);
+    assert_eq!(command.env.len(), 2);
+    assert_eq!(command.env["CI"], "1");
+
+    builder.clear_env();
+    let command = builder.executable("cargo".to_owned()).build().unwrap();
+    assert!(command.env.is_empty());
+}
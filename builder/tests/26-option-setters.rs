@@ -0,0 +1,52 @@
+// Optional fields get two companions next to their setter: `maybe_<setter>`
+// takes an `Option` to forward as is, and `unset_<setter>` clears a previously
+// set value.
+//
+// With #[builder(setter(strip_option = false))] the setter itself takes the
+// full `Option` type instead, which makes `maybe_<setter>` redundant. The
+// opt-out may also be given on the struct to apply to every optional field.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+    #[builder(setter(strip_option = false))]
+    user: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(setter(strip_option = false))]
+pub struct Limits {
+    memory: Option<u64>,
+    #[builder(setter(strip_option))]
+    cpus: Option<u32>,
+}
+
+fn main() {
+    let inherited: Option<String> = None;
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .maybe_current_dir(Some("/tmp".to_owned()))
+        .user(Some("root".to_owned()))
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.user.as_deref(), Some("root"));
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("/tmp".to_owned())
+        .maybe_current_dir(inherited)
+        .user(Some("root".to_owned()))
+        .unset_user()
+        .build()
+        .unwrap();
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.user, None);
+
+    let limits = Limits::builder().memory(Some(512)).cpus(2).build().unwrap();
+    assert_eq!(limits.memory, Some(512));
+    assert_eq!(limits.cpus, Some(2));
+}
@@ -0,0 +1,51 @@
+// Optional fields are recognized only when spelled as `Option<T>`,
+// `std::option::Option<T>` or `core::option::Option<T>`. A user type that
+// happens to be called `Option` is treated like any other mandatory field.
+//
+// Aliases can't be seen through by the macro, so #[builder(optional)] marks a
+// field as optional explicitly, and #[builder(required)] makes an `Option`
+// field mandatory with a setter taking the full `Option`.
+
+use derive_builder::Builder;
+
+mod mylib {
+    #[derive(Debug, PartialEq)]
+    pub struct Option<T>(pub T);
+}
+
+type Maybe<T> = Option<T>;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: mylib::Option<String>,
+    current_dir: std::option::Option<String>,
+    timeout: ::core::option::Option<u32>,
+    #[builder(optional)]
+    user: Maybe<String>,
+    #[builder(required)]
+    stdin: Option<String>,
+    #[builder(each = "arg")]
+    args: std::vec::Vec<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable(mylib::Option("cargo".to_owned()))
+        .user("root".to_owned())
+        .stdin(None)
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, mylib::Option("cargo".to_owned()));
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.timeout, None);
+    assert_eq!(command.user.as_deref(), Some("root"));
+    assert_eq!(command.stdin, None);
+    assert_eq!(command.args, ["build"]);
+
+    let result = Command::builder()
+        .executable(mylib::Option("cargo".to_owned()))
+        .build();
+    assert_eq!(result.err(), Some(CommandBuilderError::MissingStdin));
+}
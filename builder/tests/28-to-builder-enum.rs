@@ -0,0 +1,14 @@
+// #[builder(to_builder)] is only supported on structs. On an enum it is
+// rejected at the attribute instead of generating a conversion that cannot
+// work. This is a compile_fail test.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(to_builder)]
+pub enum Message {
+    Text { body: String },
+    Ping,
+}
+
+fn main() {}
@@ -0,0 +1,59 @@
+// A built value converts back into a builder with every field pre-populated,
+// so a base configuration can be tweaked and rebuilt. `From<Command>` consumes
+// the value; #[builder(to_builder)] additionally generates a `to_builder(&self)`
+// method cloning it, which requires the struct to implement Clone. Neither is
+// available for enums, so #[builder(to_builder)] is rejected there.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone)]
+#[builder(to_builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = "30")]
+    timeout: u64,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Request<T> {
+    url: String,
+    body: T,
+}
+
+fn main() {
+    let base = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+
+    let release = base
+        .to_builder()
+        .arg("--release".to_owned())
+        .current_dir("/tmp".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(base.args, ["build"]);
+    assert_eq!(release.args, ["build", "--release"]);
+    assert_eq!(release.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(release.timeout, 30);
+
+    let mut builder = CommandBuilder::from(release);
+    let test = builder.executable("cross".to_owned()).build().unwrap();
+    assert_eq!(test.executable, "cross");
+    assert_eq!(test.args, ["build", "--release"]);
+
+    let request = Request::builder()
+        .url("/".to_owned())
+        .body(1u8)
+        .build();
+    let request = RequestBuilder::from(request)
+        .url("/index".to_owned())
+        .build();
+    assert_eq!(request.url, "/index");
+    assert_eq!(request.body, 1);
+}
@@ -0,0 +1,50 @@
+// The builder implements Default, and `CommandBuilder::new()` is equivalent to
+// `Command::builder()`.
+//
+// #[builder(derive(...))] forwards derives onto the generated builder type, and
+// #[builder(attr(...))] forwards arbitrary attributes. Immutable builders
+// already derive Clone, so listing it again is fine.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(derive(Debug, Clone, PartialEq), attr(must_use))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable", derive(Clone, Debug))]
+pub struct Job {
+    name: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, derive(Debug))]
+pub struct Request {
+    url: String,
+}
+
+fn main() {
+    let mut builder = CommandBuilder::new();
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+
+    let copy = builder.clone();
+    assert_eq!(builder, copy);
+    assert_ne!(builder, CommandBuilder::default());
+    assert!(format!("{:?}", builder).contains("cargo"));
+
+    let command = builder.build().unwrap();
+    assert_eq!(command.args, ["build"]);
+
+    let job = JobBuilder::default().name("nightly".to_owned());
+    assert!(format!("{:?}", job).contains("nightly"));
+    assert_eq!(job.build().unwrap().name, "nightly");
+
+    let request = RequestBuilder::new().url("/".to_owned());
+    assert!(format!("{:?}", request).contains("/"));
+    assert_eq!(request.build().url, "/");
+}
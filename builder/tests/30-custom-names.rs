@@ -0,0 +1,79 @@
+// Generated names can be changed to avoid collisions with existing items:
+//
+//   - #[builder(name = "...")] renames the builder type (and with it the error
+//     type, which becomes `<name>Error`),
+//   - #[builder(constructor = "...")] renames the `builder()` constructor,
+//   - #[builder(build_fn(name = "..."))] renames the `build()` method,
+//   - #[builder(setter(prefix = "..."))] prefixes the setters, including the
+//     one-at-a-time setters of `each` fields,
+//   - a field-level #[builder(setter(name = "..."))] renames a single setter.
+//
+// Companion methods are always named after the final setter name, so with the
+// prefix `with_` the field `port` gets `with_port`, `try_with_port`,
+// `maybe_with_port` and `unset_with_port`, and a renamed setter `in_dir` gets
+// `maybe_in_dir` and `unset_in_dir`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(
+    name = "CommandSpec",
+    constructor = "spec",
+    build_fn(name = "finish"),
+    setter(prefix = "with_")
+)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(setter(name = "in_dir"))]
+    current_dir: Option<String>,
+    #[builder(setter(try_into))]
+    port: Option<u16>,
+}
+
+impl Command {
+    pub fn builder() -> &'static str {
+        "taken"
+    }
+
+    pub fn build(&self) -> String {
+        self.executable.clone()
+    }
+}
+
+fn main() {
+    let command = Command::spec()
+        .with_executable("cargo".to_owned())
+        .with_arg("build".to_owned())
+        .in_dir("/tmp".to_owned())
+        .finish()
+        .unwrap();
+
+    assert_eq!(command.build(), "cargo");
+    assert_eq!(Command::builder(), "taken");
+    assert_eq!(command.args, ["build"]);
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.port, None);
+
+    let command = Command::spec()
+        .with_executable("cargo".to_owned())
+        .with_args(vec!["build".to_owned()])
+        .clear_with_args()
+        .extend_with_args(["test".to_owned()])
+        .maybe_in_dir(Some("/tmp".to_owned()))
+        .unset_in_dir()
+        .try_with_port(8080u32)
+        .unwrap()
+        .unset_with_port()
+        .maybe_with_port(Some(443))
+        .finish()
+        .unwrap();
+
+    assert_eq!(command.args, ["test"]);
+    assert_eq!(command.current_dir, None);
+    assert_eq!(command.port, Some(443));
+
+    let result = CommandSpec::new().finish();
+    assert_eq!(result.err(), Some(CommandSpecError::MissingExecutable));
+}
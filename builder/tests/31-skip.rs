@@ -0,0 +1,37 @@
+// Fields marked #[builder(skip)] get no builder slot and no setter. They are
+// filled with `Default::default()` when building, or with the expression given
+// in #[builder(skip = "...")]. `PhantomData` fields are skipped automatically.
+
+use derive_builder::Builder;
+use std::marker::PhantomData;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(skip)]
+    runs: u32,
+    #[builder(skip = "vec![\"cached\".to_owned()]")]
+    cache: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Handle<T> {
+    id: u32,
+    marker: PhantomData<T>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.runs, 0);
+    assert_eq!(command.cache, ["cached"]);
+
+    let handle: Handle<String> = Handle::builder().id(7).build();
+    assert_eq!(handle.id, 7);
+    let _: PhantomData<String> = handle.marker;
+}
@@ -0,0 +1,52 @@
+// With #[builder(no_std)] the generated code only refers to `core` and
+// `alloc`, so it can be used from `#![no_std]` crates that link `alloc`. The
+// error type still implements Debug and Display, but not std's Error trait.
+//
+// The `std` crate is only linked here to provide the test's entry point; it is
+// renamed so that any `::std` path in the generated code fails to resolve.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as _std;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(no_std)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(validate = "short")]
+    current_dir: Option<String>,
+}
+
+fn short(dir: &String) -> Result<(), &'static str> {
+    if dir.len() < 8 {
+        Ok(())
+    } else {
+        Err("too long")
+    }
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_string())
+        .arg("build".to_string())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["build"]);
+
+    let err = Command::builder()
+        .current_dir("/home/user".to_string())
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "executable not set, invalid current_dir: too long",
+    );
+}
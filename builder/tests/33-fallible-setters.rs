@@ -0,0 +1,82 @@
+// With #[builder(setter(try_into))] every setter gets a `try_` companion
+// accepting anything that converts via TryInto, returning the conversion error
+// immediately.
+//
+// With #[builder(setter(parse))] the setter takes a `&str` and parses it with
+// FromStr. A parse failure is recorded and reported as the field's `Invalid`
+// variant by `build()`, unless the field is set successfully afterwards.
+
+use derive_builder::Builder;
+use std::num::TryFromIntError;
+
+#[derive(Builder)]
+pub struct Server {
+    #[builder(setter(try_into))]
+    port: u16,
+    #[builder(setter(parse))]
+    workers: usize,
+    #[builder(setter(parse))]
+    timeout: Option<u64>,
+}
+
+#[derive(Builder)]
+#[builder(typestate, setter(try_into))]
+pub struct Client {
+    port: u16,
+    retries: Option<u8>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Limits {
+    #[builder(setter(try_into))]
+    memory: u32,
+}
+
+fn main() -> Result<(), TryFromIntError> {
+    let server = Server::builder()
+        .try_port(8080i64)?
+        .workers("4")
+        .timeout("30")
+        .build()
+        .unwrap();
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, 4);
+    assert_eq!(server.timeout, Some(30));
+
+    assert!(Server::builder().try_port(-1).is_err());
+
+    let result = Server::builder()
+        .port(80)
+        .workers("many")
+        .timeout("soon")
+        .build();
+    let err = result.err().unwrap();
+    assert_eq!(
+        err.errors(),
+        [
+            ServerBuilderError::InvalidWorkers("invalid digit found in string".to_owned()),
+            ServerBuilderError::InvalidTimeout("invalid digit found in string".to_owned()),
+        ],
+    );
+
+    let server = Server::builder()
+        .port(80)
+        .workers("many")
+        .workers("2")
+        .timeout("soon")
+        .unset_timeout()
+        .build()
+        .unwrap();
+    assert_eq!(server.workers, 2);
+    assert_eq!(server.timeout, None);
+
+    let client = Client::builder().try_port(443u64)?.try_retries(3i32)?.build();
+    assert_eq!(client.port, 443);
+    assert_eq!(client.retries, Some(3));
+
+    let limits = Limits::builder().try_memory(512u64)?.build().unwrap();
+    assert_eq!(limits.memory, 512);
+
+    Ok(())
+}
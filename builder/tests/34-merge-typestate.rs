@@ -0,0 +1,15 @@
+// Typestate builders have no `merge` method, so #[builder(merge = "...")] on
+// one of their fields is rejected at the attribute. This is a compile_fail
+// test.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    name: String,
+    #[builder(each = "step", merge = "replace")]
+    steps: Vec<String>,
+}
+
+fn main() {}
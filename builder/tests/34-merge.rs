@@ -0,0 +1,60 @@
+// Builders can be layered with `merge`: every field set in the other builder
+// overrides this builder's value, while unset fields are left alone.
+//
+// The items of `each` fields are appended by default. With
+// #[builder(merge = "replace")] a non-empty collection in the other builder
+// replaces this builder's items instead. Typestate builders have no `merge`,
+// so the attribute is rejected there.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", merge = "replace")]
+    env: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = "30")]
+    timeout: u64,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Job {
+    name: String,
+    retries: Option<u8>,
+}
+
+fn main() {
+    let mut defaults = Command::builder();
+    defaults
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .env("RUST_LOG=info".to_owned())
+        .current_dir("/".to_owned())
+        .timeout(60);
+
+    let mut file = Command::builder();
+    file.arg("--release".to_owned()).current_dir("/src".to_owned());
+
+    let mut flags = Command::builder();
+    flags.env("RUST_LOG=debug".to_owned());
+
+    let command = defaults.merge(file).merge(flags).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env, ["RUST_LOG=debug"]);
+    assert_eq!(command.current_dir.as_deref(), Some("/src"));
+    assert_eq!(command.timeout, 60);
+
+    let job = Job::builder()
+        .name("nightly".to_owned())
+        .retries(3)
+        .merge(Job::builder().name("weekly".to_owned()))
+        .build()
+        .unwrap();
+    assert_eq!(job.name, "weekly");
+    assert_eq!(job.retries, Some(3));
+}
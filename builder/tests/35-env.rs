@@ -0,0 +1,83 @@
+// Fields marked #[builder(env = "VAR")] can be filled from the environment.
+// `CommandBuilder::from_env()` starts a builder from the environment, and
+// `fill_from_env()` fills only those fields that are not set yet. Values are
+// parsed with FromStr.
+//
+// `each` fields split the variable on "," or on the separator given in
+// #[builder(env(name = "...", separator = "..."))]; map fields expect
+// `key=value` items.
+//
+// A value that fails to parse is reported by `build()` as the `Env` variant
+// naming the variable, unless the field is set explicitly afterwards. A
+// list with one bad item leaves the field untouched.
+
+use derive_builder::Builder;
+use std::collections::BTreeMap;
+use std::env;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(env = "BUILDER_TEST_EXECUTABLE")]
+    executable: String,
+    #[builder(each = "arg", env(name = "BUILDER_TEST_ARGS", separator = " "))]
+    args: Vec<String>,
+    #[builder(each = "var", env = "BUILDER_TEST_VARS")]
+    vars: BTreeMap<String, u32>,
+    #[builder(env = "BUILDER_TEST_TIMEOUT")]
+    timeout: Option<u64>,
+    #[builder(env = "BUILDER_TEST_UNSET")]
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct Probe {
+    #[builder(each = "n", env = "BUILDER_TEST_PROBE_NS")]
+    ns: Vec<u32>,
+}
+
+fn main() {
+    env::set_var("BUILDER_TEST_EXECUTABLE", "cargo");
+    env::set_var("BUILDER_TEST_ARGS", "build --release");
+    env::set_var("BUILDER_TEST_VARS", "jobs=4,level=2");
+    env::set_var("BUILDER_TEST_TIMEOUT", "30");
+    env::remove_var("BUILDER_TEST_UNSET");
+
+    let command = CommandBuilder::from_env().build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.vars["jobs"], 4);
+    assert_eq!(command.timeout, Some(30));
+    assert_eq!(command.current_dir, None);
+
+    let command = Command::builder()
+        .executable("cross".to_owned())
+        .arg("test".to_owned())
+        .fill_from_env()
+        .build()
+        .unwrap();
+    assert_eq!(command.executable, "cross");
+    assert_eq!(command.args, ["test"]);
+    assert_eq!(command.timeout, Some(30));
+
+    env::set_var("BUILDER_TEST_TIMEOUT", "soon");
+    let err = CommandBuilder::from_env().build().err().unwrap();
+    assert_eq!(
+        err,
+        CommandBuilderError::Env {
+            var: "BUILDER_TEST_TIMEOUT",
+            message: "invalid digit found in string".to_owned(),
+        },
+    );
+    assert_eq!(
+        err.to_string(),
+        "invalid environment variable BUILDER_TEST_TIMEOUT: invalid digit found in string",
+    );
+
+    let command = CommandBuilder::from_env().timeout(5).build().unwrap();
+    assert_eq!(command.timeout, Some(5));
+
+    env::set_var("BUILDER_TEST_PROBE_NS", "1,2,x,4");
+    let probe = ProbeBuilder::from_env().n(9).build().unwrap();
+    assert_eq!(probe.ns, [9]);
+    assert!(ProbeBuilder::from_env().build().is_err());
+}
@@ -0,0 +1,34 @@
+// Every problem in the builder attributes is reported in a single compile:
+// misspelled keys come with a suggestion and the list of accepted keys, and
+// duplicate or conflicting keys are rejected, across the struct and all of
+// its fields. Builder attributes on enum variants are rejected as well, since
+// options for enums are given on the enum itself. This is a compile_fail
+// test.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(patern = "owned")]
+pub struct Command {
+    #[builder(setter(intoo))]
+    executable: String,
+    #[builder(each = "arg", defualt)]
+    args: Vec<String>,
+    #[builder(default, default = "Vec::new()")]
+    env: Vec<String>,
+    #[builder(optional, required)]
+    current_dir: Option<String>,
+    #[builder(bogus)]
+    timeout: u64,
+    #[builder(skip, setter(into))]
+    retries: u32,
+}
+
+#[derive(Builder)]
+pub enum Shape {
+    #[builder(totally_bogus = "x", typestate)]
+    Circle { radius: f64 },
+    Square { side: f64 },
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+// Using `each` on a field whose type is not a collection is reported as an
+// error on the field's type, instead of failing somewhere inside the generated
+// code. Types the macro cannot rule out by name, such as `Duration`, are
+// checked by the compiler to implement `Default` and `Extend`. This is a
+// compile_fail test.
+
+use derive_builder::Builder;
+use std::time::Duration;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: String,
+    #[builder(each = "env")]
+    env: Option<Vec<String>>,
+    #[builder(each = "dir")]
+    dirs: [String; 2],
+}
+
+#[derive(Builder)]
+pub struct Job {
+    #[builder(each = "step")]
+    timeout: Duration,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// Setter names that would be generated twice are reported on the name that
+// collides, whether it clashes with another field's setter or with one of the
+// builder's own methods. This is a compile_fail test.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "executable")]
+    args: Vec<String>,
+    #[builder(each = "clear_args")]
+    env: Vec<String>,
+    #[builder(setter(name = "build"))]
+    current_dir: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+// A default expression can only use defaulted fields declared before it, as
+// defaults are resolved in field order. Referring to a later one is reported
+// on that name instead of failing with a type mismatch inside the generated
+// code. The same goes for the expression of a skipped field. This is a
+// compile_fail test.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default = "timeout / 10")]
+    retries: u32,
+    #[builder(default = "30")]
+    timeout: u32,
+}
+
+#[derive(Builder)]
+pub struct Request {
+    #[builder(skip = "retries + 1")]
+    attempts: u32,
+    #[builder(default = "3")]
+    retries: u32,
+}
+
+fn main() {}
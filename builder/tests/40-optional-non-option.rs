@@ -0,0 +1,16 @@
+// #[builder(optional)] is meant for aliases of `Option<T>`. On an alias of
+// any other type, the mismatch is reported at the field's type instead of at
+// the derive. This is a compile_fail test.
+
+use derive_builder::Builder;
+
+type Names = Vec<String>;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(optional)]
+    groups: Names,
+}
+
+fn main() {}
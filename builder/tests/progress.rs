@@ -10,4 +10,37 @@ fn tests() {
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-typestate.rs");
+    t.compile_fail("tests/11-typestate-missing-field.rs");
+    t.pass("tests/12-error-type.rs");
+    t.pass("tests/13-all-missing-fields.rs");
+    t.pass("tests/14-default.rs");
+    t.pass("tests/15-setter-into.rs");
+    t.pass("tests/16-generics.rs");
+    t.pass("tests/17-tuple-struct.rs");
+    t.pass("tests/18-enum-variants.rs");
+    t.pass("tests/19-builder-patterns.rs");
+    t.pass("tests/20-reusable-build.rs");
+    t.pass("tests/21-visibility.rs");
+    t.compile_fail("tests/22-private-setter.rs");
+    t.pass("tests/23-validation.rs");
+    t.pass("tests/24-each-collections.rs");
+    t.pass("tests/25-extend-setters.rs");
+    t.pass("tests/26-option-setters.rs");
+    t.pass("tests/27-type-detection.rs");
+    t.pass("tests/28-to-builder.rs");
+    t.compile_fail("tests/28-to-builder-enum.rs");
+    t.pass("tests/29-builder-derives.rs");
+    t.pass("tests/30-custom-names.rs");
+    t.pass("tests/31-skip.rs");
+    t.pass("tests/32-no-std.rs");
+    t.pass("tests/33-fallible-setters.rs");
+    t.pass("tests/34-merge.rs");
+    t.compile_fail("tests/34-merge-typestate.rs");
+    t.pass("tests/35-env.rs");
+    t.compile_fail("tests/36-attribute-diagnostics.rs");
+    t.compile_fail("tests/37-each-non-collection.rs");
+    t.compile_fail("tests/38-setter-collisions.rs");
+    t.compile_fail("tests/39-default-order.rs");
+    t.compile_fail("tests/40-optional-non-option.rs");
 }